@@ -0,0 +1,207 @@
+//! Activity log: every fetch, upload, restore and profile switch with its outcome, so a group can
+//! reconstruct "who overwrote the save on Friday".
+//!
+//! Each PC appends to `activity.jsonl` in its config dir and to its own feed in the cloud,
+//! `activity/<user>@<host>.jsonl` – one file per PC, so PCs never overwrite each other's entries.
+//! The git backend has no cloud feed; its commit history already records every push.
+//! Recording never fails the operation it describes.
+
+use crate::sync::{config_dir, SyncConfig, SyncTarget};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub(crate) const LOCAL_LOG: &str = "activity.jsonl";
+const CLOUD_DIR: &str = "activity";
+/// Entries kept in a PC's cloud feed (the local log keeps everything).
+const CLOUD_FEED_LIMIT: usize = 500;
+
+pub(crate) const OUTCOME_OK: &str = "ok";
+/// Succeeded after the user overrode a warning.
+pub(crate) const OUTCOME_FORCED: &str = "forced";
+/// Stopped by a warning the user may override (progress, game running, setup mismatch).
+pub(crate) const OUTCOME_WARNING: &str = "warning";
+pub(crate) const OUTCOME_FAILED: &str = "failed";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ActivityEntry {
+    /// Unix seconds.
+    pub at: i64,
+    pub user: String,
+    pub hostname: Option<String>,
+    /// "fetch", "upload", "restore", "switch_profile", "handoff" or "accept_handoff".
+    pub action: String,
+    /// "save", "mods" or "both".
+    pub target: String,
+    /// One of the `OUTCOME_*` values.
+    pub outcome: String,
+    /// The result or error shown to the user, first line only.
+    pub message: String,
+}
+
+/// Entries matching a filter, newest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityFeed {
+    pub entries: Vec<ActivityEntry>,
+    /// Set when the cloud feeds could not be listed (`entries` then only has this PC's log) or
+    /// some of them could not be read (their entries are missing).
+    pub cloud_error: Option<String>,
+}
+
+pub(crate) fn target_name(target: SyncTarget) -> &'static str {
+    match target {
+        SyncTarget::Save => "save",
+        SyncTarget::Mods => "mods",
+        SyncTarget::Both => "both",
+    }
+}
+
+/// The text after the prefix of an error the user may override by forcing, if it is one.
+pub(crate) fn forceable_warning(error: &str) -> Option<&str> {
+    const FORCEABLE: [&str; 3] = [
+        crate::sync::PROGRESS_WARNING_PREFIX,
+        crate::game_process::GAME_RUNNING_PREFIX,
+        crate::save_meta::SAVE_MISMATCH_PREFIX,
+    ];
+    FORCEABLE
+        .iter()
+        .find_map(|p| error.find(p).map(|i| &error[i + p.len()..]))
+}
+
+fn outcome(forced: bool, result: &Result<String, String>) -> (&'static str, &str) {
+    match result {
+        Ok(message) if forced => (OUTCOME_FORCED, message),
+        Ok(message) => (OUTCOME_OK, message),
+        Err(e) => match forceable_warning(e) {
+            Some(warning) => (OUTCOME_WARNING, warning),
+            None => (OUTCOME_FAILED, e),
+        },
+    }
+}
+
+/// Cloud feed of this PC: `activity/<user>@<host>.jsonl`.
+fn feed_object(entry: &ActivityEntry) -> String {
+    format!("{}/{}.jsonl", CLOUD_DIR, crate::push_meta::pc_id(&entry.user, entry.hostname.as_deref()))
+}
+
+fn append_local(line: &str) -> Result<(), String> {
+    let path = config_dir().map_err(|e| e.to_string())?.join(LOCAL_LOG);
+    let mut f = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    writeln!(f, "{}", line).map_err(|e| e.to_string())
+}
+
+fn append_cloud(config: &SyncConfig, entry: &ActivityEntry, line: &str) -> Result<(), String> {
+    if crate::git_sync::use_git(config) {
+        return Ok(());
+    }
+    let remote = crate::blobs::Remote::from_config(config)?;
+    let object = feed_object(entry);
+    // A feed that can't be opened (e.g. written before `encrypt_archives` was turned on) is
+    // replaced by a fresh one rather than blocking this PC's feed for good.
+    let existing = match remote.get(&object)?.map(|data| crate::crypto::open_downloaded(config, data, &object)) {
+        Some(Ok(data)) => String::from_utf8_lossy(&data).to_string(),
+        Some(Err(e)) => {
+            tracing::warn!(object, error = %e, "activity feed unreadable, starting a new one");
+            String::new()
+        }
+        None => String::new(),
+    };
+    let mut lines: Vec<&str> = existing.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines.push(line);
+    let keep = lines.len().saturating_sub(CLOUD_FEED_LIMIT);
+    let text = lines[keep..].join("\n") + "\n";
+    remote.put(&object, &crate::crypto::seal_for_upload(config, text.into_bytes())?)
+}
+
+/// Log the outcome of `action` on `target`, locally and in this PC's cloud feed.
+pub(crate) fn record(config: &SyncConfig, action: &str, target: &str, forced: bool, result: &Result<String, String>) {
+    let (outcome, message) = outcome(forced, result);
+    match outcome {
+        OUTCOME_FAILED => tracing::warn!(action, target, outcome, message, "activity"),
+        _ => tracing::info!(action, target, outcome, message, "activity"),
+    }
+    let entry = ActivityEntry {
+        at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0),
+        user: crate::push_meta::uploader_name(config),
+        hostname: crate::push_meta::hostname(),
+        action: action.to_string(),
+        target: target.to_string(),
+        outcome: outcome.to_string(),
+        message: message.lines().next().unwrap_or("").to_string(),
+    };
+    let Ok(line) = serde_json::to_string(&entry) else { return };
+    let _ = append_local(&line);
+    let _ = append_cloud(config, &entry, &line);
+}
+
+fn parse_lines(text: &str) -> impl Iterator<Item = ActivityEntry> + '_ {
+    text.lines().filter_map(|l| serde_json::from_str(l).ok())
+}
+
+/// Entries of every cloud feed, and the feeds that could not be read (skipped).
+fn cloud_entries(config: &SyncConfig) -> Result<(Vec<ActivityEntry>, Vec<String>), String> {
+    if crate::git_sync::use_git(config) {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    let remote = crate::blobs::Remote::from_config(config)?;
+    let mut entries = Vec::new();
+    let mut unreadable = Vec::new();
+    for feed in remote.list(CLOUD_DIR)? {
+        let object = format!("{}/{}", CLOUD_DIR, feed.name);
+        let data = remote
+            .get(&object)
+            .and_then(|data| data.map(|data| crate::crypto::open_downloaded(config, data, &object)).transpose());
+        match data {
+            Ok(Some(data)) => entries.extend(parse_lines(&String::from_utf8_lossy(&data))),
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!(object, error = %e, "skipping unreadable activity feed");
+                unreadable.push(feed.name.trim_end_matches(".jsonl").to_string());
+            }
+        }
+    }
+    Ok((entries, unreadable))
+}
+
+/// This PC's log and every cloud feed, merged and filtered. `target` "save" or "mods" also
+/// matches "both"; `user` matches part of the name, ignoring case.
+pub fn get_activity(
+    config: &SyncConfig,
+    target: Option<&str>,
+    user: Option<&str>,
+    outcome: Option<&str>,
+    limit: usize,
+) -> Result<ActivityFeed, String> {
+    let local_text = fs::read_to_string(config_dir().map_err(|e| e.to_string())?.join(LOCAL_LOG)).unwrap_or_default();
+    let mut entries: Vec<ActivityEntry> = parse_lines(&local_text).collect();
+    let cloud_error = match cloud_entries(config) {
+        Ok((cloud, unreadable)) => {
+            // This PC's own entries are in both.
+            for entry in cloud {
+                if !entries.contains(&entry) {
+                    entries.push(entry);
+                }
+            }
+            (!unreadable.is_empty()).then(|| format!("can't read the feeds of {}", unreadable.join(", ")))
+        }
+        Err(e) => Some(e),
+    };
+
+    let user = user.map(str::trim).filter(|u| !u.is_empty()).map(str::to_lowercase);
+    entries.retain(|e| {
+        target.is_none_or(|t| e.target == t || (e.target == "both" && t != "both"))
+            && user.as_ref().is_none_or(|u| e.user.to_lowercase().contains(u))
+            && outcome.is_none_or(|o| e.outcome == o)
+    });
+    entries.sort_by_key(|e| std::cmp::Reverse(e.at));
+    entries.truncate(limit);
+    Ok(ActivityFeed { entries, cloud_error })
+}
@@ -0,0 +1,118 @@
+//! Zip archives of a synced folder (Save.zip / Mods.zip), shared by the Supabase and cloud-folder backends.
+
+use crate::manifest::ManifestEntry;
+use crate::filter::PathFilter;
+use crate::sync::clear_matching;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// Zip en mappe rekursivt til bytes (kun filer som `filter` lader igennem).
+pub(crate) fn zip_dir(path: &Path, filter: &PathFilter) -> Result<Vec<u8>, String> {
+    let mut buf = Vec::new();
+    {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut buf));
+        let options = zip::write::SimpleFileOptions::default()
+            .unix_permissions(0o755)
+            .compression_method(zip::CompressionMethod::Deflated);
+
+        fn add_dir<W: std::io::Write + std::io::Seek>(
+            zip: &mut zip::ZipWriter<W>,
+            options: zip::write::SimpleFileOptions,
+            dir: &Path,
+            prefix: &str,
+            filter: &PathFilter,
+        ) -> Result<(), String> {
+            for e in fs::read_dir(dir).map_err(|e| e.to_string())? {
+                let e = e.map_err(|e| e.to_string())?;
+                let name = e.file_name();
+                let full = dir.join(&name);
+                let entry_path = if prefix.is_empty() {
+                    name.to_string_lossy().to_string()
+                } else {
+                    format!("{}/{}", prefix, name.to_string_lossy())
+                };
+                if e.file_type().map_err(|e| e.to_string())?.is_dir() {
+                    if filter.enters(&entry_path) {
+                        add_dir(zip, options, &full, &entry_path, filter)?;
+                    }
+                } else if filter.matches(&entry_path) {
+                    zip.start_file(&entry_path, options)
+                        .map_err(|e| e.to_string())?;
+                    let mut f = fs::File::open(&full).map_err(|e| e.to_string())?;
+                    std::io::copy(&mut f, zip).map_err(|e| e.to_string())?;
+                }
+            }
+            Ok(())
+        }
+
+        if path.is_dir() {
+            add_dir(&mut zip, options, path, "", filter)?;
+        }
+        zip.finish().map_err(|e| e.to_string())?;
+    }
+    Ok(buf)
+}
+
+/// Udpak zip-bytes til en mappe. De filer `filter` lader igennem slettes først; resten røres ikke.
+pub(crate) fn unzip_to_dir(bytes: &[u8], dest: &Path, filter: &PathFilter) -> Result<(), String> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    clear_matching(dest, filter).map_err(|e| e.to_string())?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(|e| e.to_string())?;
+        let Some(name) = file.enclosed_name() else { continue };
+        let rel = name.to_string_lossy().replace('\\', "/");
+        let wanted = if file.is_dir() { filter.enters(&rel) } else { filter.matches(&rel) };
+        if rel.is_empty() || !wanted {
+            continue;
+        }
+        let out = dest.join(&name);
+        if file.is_dir() {
+            fs::create_dir_all(&out).map_err(|e| e.to_string())?;
+        } else {
+            if let Some(p) = out.parent() {
+                fs::create_dir_all(p).map_err(|e| e.to_string())?;
+            }
+            let mut out_file = fs::File::create(&out).map_err(|e| e.to_string())?;
+            std::io::copy(&mut file, &mut out_file).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a file next to its final name and rename it into place, so a cloud sync client
+/// only ever sees the complete file.
+pub(crate) fn write_file_atomic(path: &Path, data: &[u8]) -> Result<(), String> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid archive path: {}", path.display()))?;
+    let tmp = path.with_file_name(format!("{}.tmp", file_name.to_string_lossy()));
+    fs::write(&tmp, data).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        e.to_string()
+    })
+}
+
+/// Manifest entries (path, size, SHA-256) for every file in zip bytes, sorted by path, so a
+/// downloaded archive can be checked against its signed manifest before extracting.
+pub(crate) fn manifest_of_zip(bytes: &[u8]) -> Result<Vec<ManifestEntry>, String> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let mut files = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(|e| e.to_string())?;
+        if file.is_dir() {
+            continue;
+        }
+        let path = file.name().to_string();
+        let mut hasher = Sha256::new();
+        let size = std::io::copy(&mut file, &mut hasher).map_err(|e| e.to_string())?;
+        files.push(ManifestEntry {
+            path,
+            size,
+            sha256: crate::manifest::to_hex(&hasher.finalize()),
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
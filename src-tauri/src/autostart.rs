@@ -0,0 +1,177 @@
+//! Run SyncONE at login: a `Run` registry value on Windows, an XDG autostart entry
+//! (`~/.config/autostart/syncone.desktop`) on Linux. Both start this executable with
+//! `--minimized --auto-pull`, so it comes up in the tray and fetches the latest save. Other
+//! systems are not supported.
+//!
+//! The setting lives in `SyncConfig::run_at_startup`; registration is refreshed at every start so
+//! the entry follows the executable when an update moves it. Older versions had users add a
+//! shortcut to the Startup folder by hand; `legacy_entries` finds those so the UI can warn.
+
+use std::path::PathBuf;
+#[cfg(windows)]
+use std::process::Command;
+
+/// Start without showing the window (the tray icon is there).
+pub const ARG_MINIMIZED: &str = "--minimized";
+/// Fetch the latest save and mods right after starting.
+pub const ARG_AUTO_PULL: &str = "--auto-pull";
+
+#[cfg(windows)]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+const ENTRY_NAME: &str = "SyncONE";
+
+/// How this process was started, for the UI.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct LaunchOptions {
+    pub minimized: bool,
+    pub auto_pull: bool,
+}
+
+pub fn launch_options() -> LaunchOptions {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    LaunchOptions {
+        minimized: args.iter().any(|a| a == ARG_MINIMIZED),
+        auto_pull: args.iter().any(|a| a == ARG_AUTO_PULL),
+    }
+}
+
+fn current_exe() -> Result<PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("Could not find the SyncONE executable: {}", e))
+}
+
+#[cfg(windows)]
+fn reg(args: &[&str]) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let out = Command::new("reg")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Could not run reg: {}", e))?;
+    if !out.status.success() {
+        return Err(format!("reg {} failed: {}", args[0], String::from_utf8_lossy(&out.stderr).trim()));
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn register() -> Result<(), String> {
+    let command = format!("\"{}\" {} {}", current_exe()?.display(), ARG_MINIMIZED, ARG_AUTO_PULL);
+    reg(&["add", RUN_KEY, "/v", ENTRY_NAME, "/t", "REG_SZ", "/d", &command, "/f"])
+}
+
+#[cfg(windows)]
+fn unregister() -> Result<(), String> {
+    let exists = reg(&["query", RUN_KEY, "/v", ENTRY_NAME]).is_ok();
+    if !exists {
+        return Ok(());
+    }
+    reg(&["delete", RUN_KEY, "/v", ENTRY_NAME, "/f"])
+}
+
+#[cfg(target_os = "linux")]
+fn desktop_file() -> Result<PathBuf, String> {
+    let config_home = std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var("HOME").ok().map(|h| PathBuf::from(h).join(".config")))
+        .ok_or("HOME not set")?;
+    Ok(config_home.join("autostart").join("syncone.desktop"))
+}
+
+/// Quote an argument for the `Exec` key of a desktop entry.
+#[cfg(target_os = "linux")]
+fn desktop_quote(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(target_os = "linux")]
+fn register() -> Result<(), String> {
+    let path = desktop_file()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    let exe = current_exe()?;
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName={}\nComment=Fetch the latest Schedule I save at login\nExec={} {} {}\nTerminal=false\nX-GNOME-Autostart-enabled=true\n",
+        ENTRY_NAME,
+        desktop_quote(&exe.to_string_lossy()),
+        ARG_MINIMIZED,
+        ARG_AUTO_PULL
+    );
+    std::fs::write(&path, entry).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(target_os = "linux")]
+fn unregister() -> Result<(), String> {
+    let path = desktop_file()?;
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+/// Folder of login entries made by hand, and our own entry in it (never legacy).
+#[cfg(windows)]
+fn startup_dir() -> Result<(PathBuf, Option<PathBuf>), String> {
+    let appdata = std::env::var("APPDATA").map_err(|_| "APPDATA not set")?;
+    Ok((PathBuf::from(appdata).join(r"Microsoft\Windows\Start Menu\Programs\Startup"), None))
+}
+
+#[cfg(target_os = "linux")]
+fn startup_dir() -> Result<(PathBuf, Option<PathBuf>), String> {
+    let ours = desktop_file()?;
+    let dir = ours.parent().ok_or("Invalid autostart path")?.to_path_buf();
+    Ok((dir, Some(ours)))
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn startup_dir() -> Result<(PathBuf, Option<PathBuf>), String> {
+    Err("No startup folder on this system".to_string())
+}
+
+/// Startup entries for SyncONE that it did not create (e.g. `Syncone.lnk` in the Windows Startup
+/// folder). Together with the registered entry they start SyncONE twice at login.
+pub fn legacy_entries() -> Vec<PathBuf> {
+    let Ok((dir, ours)) = startup_dir() else { return Vec::new() };
+    let Ok(entries) = std::fs::read_dir(&dir) else { return Vec::new() };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| Some(p) != ours.as_ref())
+        .filter(|p| {
+            p.file_name()
+                .is_some_and(|n| n.to_string_lossy().to_lowercase().contains("syncone"))
+        })
+        .collect()
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn register() -> Result<(), String> {
+    Err("Run at startup is only supported on Windows and Linux; add SyncONE to your login items instead.".to_string())
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn unregister() -> Result<(), String> {
+    Ok(())
+}
+
+/// Register or remove the login entry.
+pub fn set_autostart(enabled: bool) -> Result<(), String> {
+    let result = if enabled { register() } else { unregister() };
+    match &result {
+        Ok(()) => tracing::info!(enabled, "autostart updated"),
+        Err(e) => tracing::warn!(enabled, error = %e, "autostart update failed"),
+    }
+    result
+}
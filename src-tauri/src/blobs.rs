@@ -0,0 +1,670 @@
+//! Content-addressed blob sync: files are stored once as `blobs/<sha256>` and a folder is
+//! described by its signed `<name>.manifest.json`. Push uploads only blobs the cloud doesn't
+//! have yet; pull downloads only missing/changed files and deletes removed ones.
+//!
+//! Every push also keeps a copy of its manifest as `versions/<name>/<push time in ms>.json`, so
+//! history costs only the files that changed. `collect_garbage` prunes old versions and deletes
+//! blobs no manifest refers to any more.
+
+use crate::crypto::{open_downloaded, seal_for_upload};
+use crate::filter::PathFilter;
+use crate::manifest::{build_manifest_filtered, sha256_hex, Manifest, ManifestEntry};
+use crate::sync::{Overrides, SyncConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const BLOB_DIR: &str = "blobs";
+const VERSION_DIR: &str = "versions";
+/// Blobs younger than this are never garbage collected: they may belong to a push whose
+/// manifest has not been uploaded yet.
+const GC_GRACE_SECS: i64 = 60 * 60;
+
+/// Folders that can be stored as blobs; their manifests (and those of the mod profiles) are the
+/// roots for garbage collection.
+const BLOB_FOLDERS: [&str; 2] = ["Save", "Mods"];
+
+/// An object in the remote store with its last-modified time (unix seconds) if known.
+pub(crate) struct RemoteObject {
+    pub(crate) name: String,
+    pub(crate) modified: Option<i64>,
+}
+
+/// One pushed version of a folder kept under `versions/<name>/`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlobVersion {
+    /// Version id (push time in Unix milliseconds; seconds for versions from before), used to
+    /// restore it.
+    pub id: String,
+    pub timestamp: i64,
+    pub file_count: usize,
+    pub total_size: u64,
+}
+
+/// Result of `collect_garbage`, for the UI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GcReport {
+    pub versions_pruned: usize,
+    pub blobs_deleted: usize,
+    pub blobs_kept: usize,
+}
+
+/// Object store behind the blob layout: the cloud folder or a Supabase bucket.
+pub(crate) enum Remote {
+    Folder(PathBuf),
+    Supabase { url: String, key: String, bucket: String },
+}
+
+impl Remote {
+    pub(crate) fn from_config(config: &SyncConfig) -> Result<Remote, String> {
+        if crate::supabase_sync::use_supabase(config) {
+            return Ok(Remote::Supabase {
+                url: config.supabase_url.clone().ok_or("Supabase URL is missing")?,
+                key: config.supabase_key.clone().ok_or("Supabase key is missing")?,
+                bucket: config.bucket_name.clone().ok_or("Bucket name is missing")?,
+            });
+        }
+        let cloud_path = config.cloud_path.as_ref().ok_or("Cloud path is not set")?;
+        Ok(Remote::Folder(PathBuf::from(cloud_path)))
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Result<Option<Vec<u8>>, String> {
+        match self {
+            Remote::Folder(root) => match fs::read(root.join(name)) {
+                Ok(data) => Ok(Some(data)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.to_string()),
+            },
+            Remote::Supabase { url, key, bucket } => {
+                crate::supabase_sync::supabase_try_download(url, key, bucket, name)
+            }
+        }
+    }
+
+    pub(crate) fn put(&self, name: &str, data: &[u8]) -> Result<(), String> {
+        match self {
+            Remote::Folder(root) => {
+                let path = root.join(name);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                crate::archive::write_file_atomic(&path, data)
+            }
+            Remote::Supabase { url, key, bucket } => {
+                crate::supabase_sync::supabase_upload(url, key, bucket, name, data)
+            }
+        }
+    }
+
+    /// Objects directly inside `dir`.
+    pub(crate) fn list(&self, dir: &str) -> Result<Vec<RemoteObject>, String> {
+        match self {
+            Remote::Folder(root) => match fs::read_dir(root.join(dir)) {
+                Ok(entries) => Ok(entries
+                    .flatten()
+                    .filter(|e| e.path().is_file())
+                    .map(|e| RemoteObject {
+                        name: e.file_name().to_string_lossy().to_string(),
+                        modified: e
+                            .metadata()
+                            .and_then(|m| m.modified())
+                            .ok()
+                            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                            .map(|d| d.as_secs() as i64),
+                    })
+                    .collect()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+                Err(e) => Err(e.to_string()),
+            },
+            Remote::Supabase { url, key, bucket } => Ok(crate::supabase_sync::supabase_list(url, key, bucket, dir)?
+                .into_iter()
+                .filter(|o| o.id.is_some())
+                .map(|o| RemoteObject {
+                    modified: o
+                        .updated_at
+                        .as_ref()
+                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+                        .map(|dt| dt.timestamp()),
+                    name: o.name,
+                })
+                .collect()),
+        }
+    }
+
+    /// Names of the folders directly inside `dir`.
+    pub(crate) fn list_dirs(&self, dir: &str) -> Result<Vec<String>, String> {
+        match self {
+            Remote::Folder(root) => match fs::read_dir(root.join(dir)) {
+                Ok(entries) => Ok(entries
+                    .flatten()
+                    .filter(|e| e.path().is_dir())
+                    .map(|e| e.file_name().to_string_lossy().to_string())
+                    .collect()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+                Err(e) => Err(e.to_string()),
+            },
+            Remote::Supabase { url, key, bucket } => Ok(crate::supabase_sync::supabase_list(url, key, bucket, dir)?
+                .into_iter()
+                .filter(|o| o.id.is_none())
+                .map(|o| o.name)
+                .collect()),
+        }
+    }
+
+    /// Delete objects by their full name; missing ones are ignored.
+    pub(crate) fn delete(&self, names: &[String]) -> Result<(), String> {
+        match self {
+            Remote::Folder(root) => {
+                for name in names {
+                    match fs::remove_file(root.join(name)) {
+                        Ok(()) => {}
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                        Err(e) => return Err(format!("{}: {}", name, e)),
+                    }
+                }
+                Ok(())
+            }
+            Remote::Supabase { url, key, bucket } => {
+                // The API takes a bounded list per request.
+                for chunk in names.chunks(500) {
+                    crate::supabase_sync::supabase_delete(url, key, bucket, chunk)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Name of the object whose timestamp tells when `name` (Save or Mods) was last pushed:
+/// `<name>.manifest.json` when the folder is stored as blobs, else `<name>.zip`.
+pub(crate) fn cloud_object_name(config: &SyncConfig, name: &str) -> String {
+    if uses_blobs(config, name) {
+        format!("{}.manifest.json", name)
+    } else {
+        format!("{}.zip", name)
+    }
+}
+
+/// Whether `name` (Save or Mods, see `profiles::cloud_name`) is synced file-by-file through the
+/// blob store.
+pub(crate) fn uses_blobs(config: &SyncConfig, name: &str) -> bool {
+    config.blob_store && (BLOB_FOLDERS.contains(&name) || crate::profiles::is_mods(name))
+}
+
+fn blob_name(sha256: &str) -> String {
+    format!("{}/{}", BLOB_DIR, sha256)
+}
+
+fn version_name(name: &str, id: &str, ext: &str) -> String {
+    format!("{}/{}/{}.{}", VERSION_DIR, name, id, ext)
+}
+
+/// Reject manifest paths that would escape the target folder.
+fn safe_relative_path(path: &str) -> Option<PathBuf> {
+    let p = Path::new(path);
+    p.components()
+        .all(|c| matches!(c, Component::Normal(_)))
+        .then(|| p.to_path_buf())
+}
+
+/// Upload the blobs the synced files of `local` need that the cloud doesn't have, then its
+/// signed manifest. Returns how many blobs were uploaded.
+pub(crate) fn push_dir(config: &SyncConfig, remote: &Remote, name: &str, local: &Path, filter: &PathFilter) -> Result<usize, String> {
+    let (manifest_bytes, sig_bytes) = crate::signing::signed_manifest(name, local, filter)?;
+    let manifest: Manifest = serde_json::from_slice(&manifest_bytes).map_err(|e| e.to_string())?;
+    let mut existing: HashSet<String> = remote.list(BLOB_DIR)?.into_iter().map(|o| o.name).collect();
+
+    let mut uploaded = 0;
+    for entry in &manifest.files {
+        if existing.contains(&entry.sha256) {
+            continue;
+        }
+        let data = fs::read(local.join(&entry.path)).map_err(|e| format!("{}: {}", entry.path, e))?;
+        remote.put(&blob_name(&entry.sha256), &seal_for_upload(config, data)?)?;
+        existing.insert(entry.sha256.clone());
+        uploaded += 1;
+    }
+
+    let sealed_sig = seal_for_upload(config, sig_bytes)?;
+    let sealed_manifest = seal_for_upload(config, manifest_bytes)?;
+    // Milliseconds, so two pushes in the same second don't overwrite each other's version.
+    let id = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+        .to_string();
+    remote.put(&version_name(name, &id, "sig"), &sealed_sig)?;
+    remote.put(&version_name(name, &id, "json"), &sealed_manifest)?;
+    // Current manifest last: it is what makes the new version visible to others.
+    remote.put(&format!("{}.manifest.sig", name), &sealed_sig)?;
+    remote.put(&format!("{}.manifest.json", name), &sealed_manifest)?;
+    Ok(uploaded)
+}
+
+/// Bring the synced files of `local` in line with the cloud manifest for `name`.
+/// Returns false if they already matched.
+pub(crate) fn pull_dir(config: &SyncConfig, remote: &Remote, name: &str, local: &Path, filter: &PathFilter) -> Result<bool, String> {
+    let manifest_object = format!("{}.manifest.json", name);
+    let sig_object = format!("{}.manifest.sig", name);
+    match fetch_signed_manifest(config, remote, name, &manifest_object, &sig_object)? {
+        Some(manifest) => apply_manifest(config, remote, name, &manifest, local, filter),
+        None => Ok(false),
+    }
+}
+
+/// Bring the local Save or Mods folder back to an earlier version (see `list_versions`).
+/// Only this PC changes; push afterwards to make it the group's version.
+pub fn restore_version(config: &SyncConfig, name: &str, id: &str, overrides: Overrides) -> Result<String, String> {
+    if !overrides.allow_game_running {
+        crate::game_process::ensure_game_not_running(config, "restore")?;
+    }
+    let (local, filter) = crate::sync::local_target(config, name)?;
+    let local = local.as_path();
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Unknown {} version: {}", name, id));
+    }
+    let remote = Remote::from_config(config)?;
+    let cloud = crate::profiles::cloud_name(config, name);
+    let manifest = fetch_signed_manifest(
+        config,
+        &remote,
+        &cloud,
+        &version_name(&cloud, id, "json"),
+        &version_name(&cloud, id, "sig"),
+    )?
+    .ok_or_else(|| format!("Unknown {} version: {}", name, id))?;
+    apply_manifest(config, &remote, &cloud, &manifest, local, &filter)?;
+    if name == "Save" {
+        crate::sync::inject_has_exited_rv(local)?;
+    }
+    Ok(format!("{} restored to the version from {}.", name, format_timestamp(manifest.created_at)))
+}
+
+fn format_timestamp(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+/// Download, decrypt and signature-check a manifest. None if it does not exist.
+fn fetch_signed_manifest(
+    config: &SyncConfig,
+    remote: &Remote,
+    name: &str,
+    manifest_object: &str,
+    sig_object: &str,
+) -> Result<Option<Manifest>, String> {
+    let manifest_bytes = match remote.get(manifest_object)? {
+        Some(data) => open_downloaded(config, data, manifest_object)?,
+        None => return Ok(None),
+    };
+    let sig_bytes = remote
+        .get(sig_object)?
+        .map(|data| open_downloaded(config, data, sig_object))
+        .transpose()?;
+    let manifest: Manifest = serde_json::from_slice(&manifest_bytes)
+        .map_err(|e| format!("{} manifest is unreadable: {}", name, e))?;
+    // Signature and trust check; each blob's content is checked against its hash when staged.
+    crate::signing::verify_download(config, name, Some(&manifest_bytes), sig_bytes.as_deref(), &manifest.files)?;
+    Ok(Some(manifest))
+}
+
+/// Download only the files of the current cloud `name` whose path matches `wanted` into `dst`
+/// (e.g. just the Money.json files of a save for the progress check). Returns false if there is
+/// no cloud manifest.
+pub(crate) fn fetch_files(
+    config: &SyncConfig,
+    remote: &Remote,
+    name: &str,
+    dst: &Path,
+    wanted: impl Fn(&str) -> bool,
+) -> Result<bool, String> {
+    let manifest_object = format!("{}.manifest.json", name);
+    let sig_object = format!("{}.manifest.sig", name);
+    let manifest = match fetch_signed_manifest(config, remote, name, &manifest_object, &sig_object)? {
+        Some(m) => m,
+        None => return Ok(false),
+    };
+    let entries: Vec<&ManifestEntry> = manifest.files.iter().filter(|e| wanted(&e.path)).collect();
+    stage_blobs(config, remote, &entries, dst)?;
+    Ok(true)
+}
+
+/// Make the synced files of `local` match `manifest`: fetch missing/changed files, delete the
+/// rest. Files the filter leaves out are not touched. Returns false if they already matched.
+fn apply_manifest(
+    config: &SyncConfig,
+    remote: &Remote,
+    name: &str,
+    manifest: &Manifest,
+    local: &Path,
+    filter: &PathFilter,
+) -> Result<bool, String> {
+    filter.check_layout(name, manifest.files.iter().map(|e| e.path.as_str()))?;
+    let local_files: HashMap<String, ManifestEntry> = if local.exists() {
+        build_manifest_filtered(local, filter)?
+            .files
+            .into_iter()
+            .map(|e| (e.path.clone(), e))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let wanted: HashSet<&str> = manifest.files.iter().map(|e| e.path.as_str()).collect();
+    let to_fetch: Vec<&ManifestEntry> = manifest
+        .files
+        .iter()
+        .filter(|e| filter.matches(&e.path) && local_files.get(&e.path) != Some(e))
+        .collect();
+    let to_delete: Vec<&String> = local_files.keys().filter(|p| !wanted.contains(p.as_str())).collect();
+    if to_fetch.is_empty() && to_delete.is_empty() {
+        return Ok(false);
+    }
+
+    // Download everything into a staging dir first so a failed pull leaves the folder untouched.
+    let staging = std::env::temp_dir().join(format!(
+        "syncone_blobs_{}",
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+    ));
+    let staged = stage_blobs(config, remote, &to_fetch, &staging);
+    let applied = staged.and_then(|_| {
+        for entry in &to_fetch {
+            let rel = safe_relative_path(&entry.path).ok_or("Invalid path in manifest")?;
+            let dst = local.join(&rel);
+            if let Some(parent) = dst.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            fs::copy(staging.join(&rel), &dst).map_err(|e| e.to_string())?;
+        }
+        for path in &to_delete {
+            let _ = fs::remove_file(local.join(path));
+        }
+        remove_empty_dirs(local, "", filter);
+        Ok(())
+    });
+    let _ = fs::remove_dir_all(&staging);
+    applied?;
+    Ok(true)
+}
+
+/// Version ids of `name` in the cloud, newest first.
+fn version_ids(remote: &Remote, name: &str) -> Result<Vec<i64>, String> {
+    let mut ids: Vec<i64> = remote
+        .list(&format!("{}/{}", VERSION_DIR, name))?
+        .into_iter()
+        .filter_map(|o| o.name.strip_suffix(".json")?.parse().ok())
+        .collect();
+    ids.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(ids)
+}
+
+/// The most recent `limit` versions of `name` (Save or Mods), newest first.
+pub fn list_versions(config: &SyncConfig, name: &str, limit: usize) -> Result<Vec<BlobVersion>, String> {
+    let remote = Remote::from_config(config)?;
+    let name = &crate::profiles::cloud_name(config, name);
+    let mut versions = Vec::new();
+    for id in version_ids(&remote, name)?.into_iter().take(limit) {
+        let object = version_name(name, &id.to_string(), "json");
+        let Some(data) = remote.get(&object)? else { continue };
+        let manifest: Manifest = serde_json::from_slice(&open_downloaded(config, data, &object)?)
+            .map_err(|e| format!("{} is unreadable: {}", object, e))?;
+        versions.push(BlobVersion {
+            id: id.to_string(),
+            timestamp: manifest.created_at,
+            file_count: manifest.files.len(),
+            total_size: manifest.files.iter().map(|e| e.size).sum(),
+        });
+    }
+    Ok(versions)
+}
+
+/// Delete all but the newest `keep_versions` versions of each folder (when given), then every
+/// blob that no remaining manifest refers to. Blobs uploaded in the last hour are kept so a
+/// push running at the same time is not broken.
+pub fn collect_garbage(config: &SyncConfig, keep_versions: Option<usize>) -> Result<GcReport, String> {
+    let remote = Remote::from_config(config)?;
+    let mut versions_pruned = 0;
+    let mut manifests = Vec::new();
+    let mut roots: Vec<String> = BLOB_FOLDERS.iter().map(|n| n.to_string()).collect();
+    roots.extend(crate::profiles::profile_cloud_names(&remote)?);
+    for name in &roots {
+        let ids = version_ids(&remote, name)?;
+        let keep = keep_versions.unwrap_or(usize::MAX).max(1).min(ids.len());
+        let pruned: Vec<String> = ids[keep..]
+            .iter()
+            .flat_map(|id| {
+                let id = id.to_string();
+                [version_name(name, &id, "json"), version_name(name, &id, "sig")]
+            })
+            .collect();
+        remote.delete(&pruned)?;
+        versions_pruned += ids.len() - keep;
+        manifests.extend(ids[..keep].iter().map(|id| version_name(name, &id.to_string(), "json")));
+        manifests.push(format!("{}.manifest.json", name));
+    }
+
+    // A manifest we cannot read could reference any blob, so refuse to delete anything then.
+    let mut referenced = HashSet::new();
+    for object in &manifests {
+        let Some(data) = remote.get(object)? else { continue };
+        let manifest: Manifest = serde_json::from_slice(&open_downloaded(config, data, object)?)
+            .map_err(|e| format!("{} is unreadable, not collecting garbage: {}", object, e))?;
+        referenced.extend(manifest.files.into_iter().map(|e| e.sha256));
+    }
+
+    let cutoff = chrono::Utc::now().timestamp() - GC_GRACE_SECS;
+    let (unreferenced, kept): (Vec<RemoteObject>, Vec<RemoteObject>) = remote
+        .list(BLOB_DIR)?
+        .into_iter()
+        .partition(|o| !referenced.contains(&o.name) && o.modified.is_some_and(|t| t < cutoff));
+    let unreferenced: Vec<String> = unreferenced.into_iter().map(|o| blob_name(&o.name)).collect();
+    remote.delete(&unreferenced)?;
+    Ok(GcReport {
+        versions_pruned,
+        blobs_deleted: unreferenced.len(),
+        blobs_kept: kept.len(),
+    })
+}
+
+/// Download and verify each blob into `staging/<path>`.
+fn stage_blobs(config: &SyncConfig, remote: &Remote, entries: &[&ManifestEntry], staging: &Path) -> Result<(), String> {
+    for entry in entries {
+        let rel = safe_relative_path(&entry.path)
+            .ok_or_else(|| format!("Refusing unsafe path in manifest: {}", entry.path))?;
+        let object = blob_name(&entry.sha256);
+        let data = remote
+            .get(&object)?
+            .ok_or_else(|| format!("Cloud copy still syncing: blob for {} is missing.", entry.path))?;
+        let data = open_downloaded(config, data, &entry.path)?;
+        if sha256_hex(&data) != entry.sha256 {
+            return Err(format!("Blob for {} does not match its hash – it may be corrupt or tampered with.", entry.path));
+        }
+        let out = staging.join(&rel);
+        if let Some(parent) = out.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(&out, data).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Remove synced directories left empty after deleting files (the root itself is kept).
+fn remove_empty_dirs(dir: &Path, prefix: &str, filter: &PathFilter) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for e in entries.flatten() {
+            let p = e.path();
+            let name = e.file_name();
+            let rel = if prefix.is_empty() {
+                name.to_string_lossy().to_string()
+            } else {
+                format!("{}/{}", prefix, name.to_string_lossy())
+            };
+            if p.is_dir() && filter.enters(&rel) {
+                remove_empty_dirs(&p, &rel, filter);
+                if filter.may_remove_dir(&rel) {
+                    let _ = fs::remove_dir(&p);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_manifest, blob_name, collect_garbage, restore_version, version_name, Remote, GC_GRACE_SECS};
+    use crate::filter::PathFilter;
+    use crate::manifest::{sha256_hex, Manifest, ManifestEntry};
+    use crate::sync::{Overrides, SyncConfig};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    /// A fresh `<tmp>/syncone_blobs_<test>/` with `cloud/` as the remote and `Save/` as the
+    /// local save.
+    fn setup(test: &str) -> (PathBuf, SyncConfig, Remote) {
+        let root = std::env::temp_dir().join(format!("syncone_blobs_{}_{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("Save")).unwrap();
+        let config = SyncConfig {
+            cloud_path: Some(root.join("cloud").to_string_lossy().to_string()),
+            save_path: Some(root.join("Save").to_string_lossy().to_string()),
+            blob_store: true,
+            ..Default::default()
+        };
+        let remote = Remote::Folder(root.join("cloud"));
+        (root, config, remote)
+    }
+
+    /// Manifest entry for `path` with `contents`, uploading the blob if `upload`.
+    fn entry(remote: &Remote, path: &str, contents: &str, upload: bool) -> ManifestEntry {
+        let sha256 = sha256_hex(contents.as_bytes());
+        if upload {
+            remote.put(&blob_name(&sha256), contents.as_bytes()).unwrap();
+        }
+        ManifestEntry { path: path.to_string(), size: contents.len() as u64, sha256 }
+    }
+
+    fn manifest(files: Vec<ManifestEntry>) -> Manifest {
+        Manifest { created_at: 1_700_000_000, name: Some("Save".to_string()), profile: None, files }
+    }
+
+    fn read(dir: &Path, path: &str) -> Option<String> {
+        fs::read_to_string(dir.join(path)).ok()
+    }
+
+    fn put_manifest(remote: &Remote, object: &str, files: Vec<ManifestEntry>) {
+        remote.put(object, &serde_json::to_vec(&manifest(files)).unwrap()).unwrap();
+    }
+
+    /// Pretend the blob for `entry` was uploaded `secs` ago.
+    fn age_blob(root: &Path, entry: &ManifestEntry, secs: i64) {
+        let file = fs::File::options().write(true).open(root.join("cloud").join(blob_name(&entry.sha256))).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(secs as u64)).unwrap();
+    }
+
+    #[test]
+    fn apply_manifest_stages_everything_before_touching_local_files() {
+        let (root, config, remote) = setup("apply");
+        let local = root.join("Save");
+        fs::create_dir_all(local.join("SaveGame_1")).unwrap();
+        fs::write(local.join("SaveGame_1/Game.json"), "old").unwrap();
+        fs::write(local.join("SaveGame_1/Same.json"), "same").unwrap();
+        fs::write(local.join("SaveGame_1/Gone.json"), "gone").unwrap();
+        let filter = PathFilter::all();
+
+        let game = entry(&remote, "SaveGame_1/Game.json", "new", true);
+        let same = entry(&remote, "SaveGame_1/Same.json", "same", false);
+        let added = entry(&remote, "SaveGame_1/Added.json", "added", false);
+        let wanted = manifest(vec![added.clone(), game, same]);
+
+        // A blob not uploaded yet: nothing local changes.
+        let err = apply_manifest(&config, &remote, "Save", &wanted, &local, &filter).unwrap_err();
+        assert!(err.contains("still syncing"), "{}", err);
+        // A blob that doesn't match its hash: same.
+        remote.put(&blob_name(&added.sha256), b"tampered").unwrap();
+        let err = apply_manifest(&config, &remote, "Save", &wanted, &local, &filter).unwrap_err();
+        assert!(err.contains("does not match its hash"), "{}", err);
+        assert_eq!(read(&local, "SaveGame_1/Game.json").as_deref(), Some("old"));
+        assert_eq!(read(&local, "SaveGame_1/Gone.json").as_deref(), Some("gone"));
+        assert_eq!(read(&local, "SaveGame_1/Added.json"), None);
+
+        remote.put(&blob_name(&added.sha256), b"added").unwrap();
+        assert!(apply_manifest(&config, &remote, "Save", &wanted, &local, &filter).unwrap());
+        assert_eq!(read(&local, "SaveGame_1/Game.json").as_deref(), Some("new"));
+        assert_eq!(read(&local, "SaveGame_1/Same.json").as_deref(), Some("same"));
+        assert_eq!(read(&local, "SaveGame_1/Added.json").as_deref(), Some("added"));
+        assert_eq!(read(&local, "SaveGame_1/Gone.json"), None);
+        // Already in line: nothing to do.
+        assert!(!apply_manifest(&config, &remote, "Save", &wanted, &local, &filter).unwrap());
+
+        let escaping = manifest(vec![entry(&remote, "../outside.json", "x", true)]);
+        let err = apply_manifest(&config, &remote, "Save", &escaping, &local, &filter).unwrap_err();
+        assert!(err.contains("unsafe path"), "{}", err);
+        assert!(!root.join("outside.json").exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn restore_version_only_takes_known_numeric_ids() {
+        let (root, config, remote) = setup("restore");
+        let overrides = Overrides { allow_game_running: true, ..Default::default() };
+        for id in ["", "../1700000000000", "17000a", "1/2", "-1"] {
+            let err = restore_version(&config, "Save", id, overrides).unwrap_err();
+            assert_eq!(err, format!("Unknown Save version: {}", id));
+        }
+        let err = restore_version(&config, "Save", "1700000000000", overrides).unwrap_err();
+        assert_eq!(err, "Unknown Save version: 1700000000000");
+
+        let game = entry(&remote, "SaveGame_1/Game.json", "v1", true);
+        put_manifest(&remote, &version_name("Save", "1700000000000", "json"), vec![game]);
+        restore_version(&config, "Save", "1700000000000", overrides).unwrap();
+        assert_eq!(read(&root.join("Save"), "SaveGame_1/Game.json").as_deref(), Some("v1"));
+        assert!(root.join("Save/SaveGame_1/Variables/HasExitedRV.json").exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn garbage_collection_keeps_referenced_and_recent_blobs() {
+        let (root, config, remote) = setup("gc");
+        let current = entry(&remote, "SaveGame_1/Game.json", "current", true);
+        let pruned_only = entry(&remote, "SaveGame_1/Game.json", "pruned version", true);
+        let profile_mod = entry(&remote, "Mods/Coop.dll", "coop", true);
+        let orphan = entry(&remote, "orphan", "orphan", true);
+        let fresh_orphan = entry(&remote, "fresh", "pushed a minute ago", true);
+        for old in [&current, &pruned_only, &profile_mod, &orphan] {
+            age_blob(&root, old, GC_GRACE_SECS + 60);
+        }
+        put_manifest(&remote, &version_name("Save", "1000", "json"), vec![pruned_only.clone()]);
+        put_manifest(&remote, &version_name("Save", "2000", "json"), vec![current.clone()]);
+        put_manifest(&remote, "Save.manifest.json", vec![current.clone()]);
+        put_manifest(&remote, "mod_profiles/coop/Mods.manifest.json", vec![profile_mod.clone()]);
+
+        // Without pruning, every version still references its blobs.
+        let report = collect_garbage(&config, None).unwrap();
+        assert_eq!((report.versions_pruned, report.blobs_deleted, report.blobs_kept), (0, 1, 4));
+
+        let report = collect_garbage(&config, Some(1)).unwrap();
+        assert_eq!((report.versions_pruned, report.blobs_deleted, report.blobs_kept), (1, 1, 3));
+        let blob_exists = |e: &ManifestEntry| root.join("cloud").join(blob_name(&e.sha256)).exists();
+        assert!(blob_exists(&current));
+        assert!(blob_exists(&profile_mod));
+        assert!(blob_exists(&fresh_orphan));
+        assert!(!blob_exists(&pruned_only));
+        assert!(!blob_exists(&orphan));
+        assert!(!root.join("cloud").join(version_name("Save", "1000", "json")).exists());
+        assert!(root.join("cloud").join(version_name("Save", "2000", "json")).exists());
+
+        // An unreadable manifest could reference anything: delete nothing.
+        remote.put("Mods.manifest.json", b"not json").unwrap();
+        age_blob(&root, &fresh_orphan, GC_GRACE_SECS + 60);
+        let err = collect_garbage(&config, None).unwrap_err();
+        assert!(err.contains("not collecting garbage"), "{}", err);
+        assert!(blob_exists(&fresh_orphan));
+        let _ = fs::remove_dir_all(&root);
+    }
+}
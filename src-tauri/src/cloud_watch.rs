@@ -0,0 +1,375 @@
+//! Push notifications: instead of waiting for the next status poll, SyncONE watches the cloud and
+//! tells the UI as soon as someone else pushes ("New save pushed by Anna").
+//!
+//! Every push writes `<name>.meta.json` last (see `push_meta`), so that object changing means a
+//! push has finished. Supabase is watched through Realtime: a websocket subscription to the
+//! bucket's rows in `storage.objects`, which must be in the `supabase_realtime` publication (see
+//! README). A cloud folder is watched with the OS file watcher. Git is not watched. While no watch
+//! is running the UI polls as before.
+//!
+//! The host lease (`handoff`) is watched the same way, so a player named as the next host is
+//! asked right away.
+
+use crate::sync::{load_config, SyncConfig};
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+/// Wait before reconnecting after the watch failed.
+const RETRY_SECS: u64 = 60;
+/// How often a running watch checks whether the settings still point at the same cloud.
+const CONFIG_CHECK_SECS: u64 = 10;
+/// A cloud folder is handled once no file has changed for this long (sync clients write in bursts).
+const SETTLE_MS: u64 = 2000;
+/// Realtime drops connections that stay silent for longer than a minute.
+const REALTIME_HEARTBEAT_SECS: u64 = 25;
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Someone else finished a push; sent to the UI as the `cloud-changed` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct CloudChange {
+    /// "save" or "mods".
+    pub target: String,
+    pub pushed_by: Option<String>,
+    pub pushed_at: Option<i64>,
+    pub note: Option<String>,
+    /// Ready to show, e.g. "New save pushed by Anna: finished the warehouse".
+    pub message: String,
+}
+
+pub enum WatchEvent {
+    Changed(CloudChange),
+    /// The host lease names this user as the next host.
+    HandoffOffered(crate::handoff::HostLease),
+    /// Someone else handed off or took over.
+    LeaseChanged(crate::handoff::HostLease),
+    /// The watch started (true) or stopped (false); the UI polls less while it runs.
+    Active(bool),
+}
+
+/// Whether a watch is running right now.
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// What is being watched; a change in the settings restarts the watch.
+#[derive(Debug, Clone, PartialEq)]
+enum Watched {
+    Nothing,
+    Folder(PathBuf),
+    Supabase { url: String, key: String, bucket: String },
+}
+
+fn watched(config: &SyncConfig) -> Watched {
+    if crate::git_sync::use_git(config) {
+        return Watched::Nothing;
+    }
+    if crate::supabase_sync::use_supabase(config) {
+        return match (&config.supabase_url, &config.supabase_key, &config.bucket_name) {
+            (Some(url), Some(key), Some(bucket)) => Watched::Supabase {
+                url: url.clone(),
+                key: key.clone(),
+                bucket: bucket.clone(),
+            },
+            _ => Watched::Nothing,
+        };
+    }
+    match config.cloud_path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        Some(path) => Watched::Folder(PathBuf::from(path)),
+        None => Watched::Nothing,
+    }
+}
+
+fn still_watching(watching: &Watched) -> bool {
+    load_config().is_ok_and(|config| &watched(&config) == watching)
+}
+
+fn set_active(active: bool, emit: &impl Fn(WatchEvent)) {
+    if ACTIVE.swap(active, Ordering::Relaxed) != active {
+        emit(WatchEvent::Active(active));
+    }
+}
+
+/// Watch the configured cloud for the lifetime of the app, following settings changes.
+pub fn start(emit: impl Fn(WatchEvent) + Send + 'static) {
+    std::thread::spawn(move || loop {
+        let watching = load_config().map(|config| watched(&config)).unwrap_or(Watched::Nothing);
+        let result = match &watching {
+            Watched::Nothing => {
+                std::thread::sleep(Duration::from_secs(CONFIG_CHECK_SECS));
+                continue;
+            }
+            Watched::Folder(dir) => watch_folder(dir, &watching, &emit),
+            Watched::Supabase { url, key, bucket } => watch_realtime(url, key, bucket, &watching, &emit),
+        };
+        set_active(false, &emit);
+        match result {
+            Ok(()) => tracing::info!("cloud settings changed, restarting the cloud watch"),
+            Err(e) => {
+                tracing::warn!(error = %e, "cloud watch stopped, polling until it reconnects");
+                std::thread::sleep(Duration::from_secs(RETRY_SECS));
+            }
+        }
+    });
+}
+
+/// The event for a changed cloud object: a handoff or change of host by someone else, or a push of
+/// the save or the active mod profile by another PC.
+fn event_for(object: &str) -> Option<WatchEvent> {
+    let config = load_config().ok()?;
+    if object == crate::handoff::LEASE_OBJECT {
+        let lease = crate::handoff::get_host_lease(&config).ok().flatten()?;
+        if crate::handoff::offered_to_us(&config, &lease) {
+            return Some(WatchEvent::HandoffOffered(lease));
+        }
+        return (!crate::handoff::changed_by_us(&config, &lease)).then_some(WatchEvent::LeaseChanged(lease));
+    }
+    change_for(&config, object).map(WatchEvent::Changed)
+}
+
+fn change_for(config: &SyncConfig, object: &str) -> Option<CloudChange> {
+    let name = object.strip_suffix(".meta.json")?;
+    let what = if name == "Save" {
+        "save"
+    } else if name == crate::profiles::cloud_name(config, "Mods") {
+        "mods"
+    } else {
+        return None;
+    };
+    let meta = crate::push_meta::cloud_push_meta(config, name).ok().flatten();
+    if let Some(meta) = &meta {
+        let ours = meta.pushed_by.as_deref() == Some(crate::push_meta::uploader_name(config).as_str())
+            && meta.hostname == crate::push_meta::hostname();
+        if ours {
+            return None;
+        }
+    }
+    let pushed_by = meta.as_ref().and_then(|m| m.pushed_by.clone());
+    let note = meta.as_ref().and_then(|m| m.note.clone());
+    let mut message = format!("New {} pushed by {}", what, pushed_by.as_deref().unwrap_or("someone"));
+    if let Some(note) = &note {
+        message.push_str(&format!(": {}", note));
+    }
+    tracing::info!(object, pushed_by = ?pushed_by, "cloud changed");
+    Some(CloudChange {
+        target: what.to_string(),
+        pushed_by,
+        pushed_at: meta.as_ref().map(|m| m.pushed_at),
+        note,
+        message,
+    })
+}
+
+/// The cloud object at `path` under the watched folder `dir`, e.g.
+/// `mod_profiles/modded/Mods.meta.json`, if it is one the watch reacts to.
+fn watched_object(dir: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(dir).ok()?;
+    let object = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    (object.ends_with(".meta.json") || object == crate::handoff::LEASE_OBJECT).then_some(object)
+}
+
+/// Watched objects under a folder that just appeared: files written into it before the watch
+/// covered it send no event of their own.
+fn watched_objects_in(dir: &Path, folder: &Path, out: &mut HashSet<String>) {
+    let Ok(entries) = std::fs::read_dir(folder) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            watched_objects_in(dir, &path, out);
+        } else {
+            out.extend(watched_object(dir, &path));
+        }
+    }
+}
+
+fn watch_folder(dir: &Path, watching: &Watched, emit: &impl Fn(WatchEvent)) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| format!("Could not watch {}: {}", dir.display(), e))?;
+    // Recursive: the records of named mod profiles live under `mod_profiles/<profile>/`.
+    watcher
+        .watch(dir, RecursiveMode::Recursive)
+        .map_err(|e| format!("Could not watch {}: {}", dir.display(), e))?;
+    tracing::info!(dir = %dir.display(), "watching cloud folder");
+    set_active(true, emit);
+    // Events carry resolved paths when the configured one goes through a link.
+    let resolved = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+
+    let mut changed: HashSet<String> = HashSet::new();
+    let mut last_change = Instant::now();
+    let mut last_config_check = Instant::now();
+    loop {
+        match rx.recv_timeout(Duration::from_millis(500)) {
+            Ok(Ok(event)) => {
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    for path in &event.paths {
+                        let root = if path.starts_with(dir) { dir } else { resolved.as_path() };
+                        if matches!(event.kind, EventKind::Create(_)) && path.is_dir() {
+                            watched_objects_in(root, path, &mut changed);
+                        }
+                        changed.extend(watched_object(root, path));
+                    }
+                    last_change = Instant::now();
+                }
+            }
+            Ok(Err(e)) => return Err(e.to_string()),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Err("The folder watcher stopped".to_string()),
+        }
+        if !changed.is_empty() && last_change.elapsed() >= Duration::from_millis(SETTLE_MS) {
+            for object in changed.drain() {
+                if let Some(event) = event_for(&object) {
+                    emit(event);
+                }
+            }
+        }
+        if last_config_check.elapsed() >= Duration::from_secs(CONFIG_CHECK_SECS) {
+            if !still_watching(watching) {
+                return Ok(());
+            }
+            last_config_check = Instant::now();
+        }
+    }
+}
+
+/// `https://<project>.supabase.co` → `wss://<project>.supabase.co/realtime/v1/websocket?...`.
+fn realtime_url(url: &str, key: &str) -> String {
+    let url = url.trim_end_matches('/');
+    let ws = if let Some(rest) = url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        url.to_string()
+    };
+    format!("{}/realtime/v1/websocket?apikey={}&vsn=1.0.0", ws, key)
+}
+
+fn send(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>, message: Value) -> Result<(), String> {
+    socket
+        .send(Message::Text(message.to_string()))
+        .map_err(|e| format!("Realtime send failed: {}", e))
+}
+
+fn watch_realtime(url: &str, key: &str, bucket: &str, watching: &Watched, emit: &impl Fn(WatchEvent)) -> Result<(), String> {
+    let (mut socket, _) =
+        tungstenite::connect(realtime_url(url, key)).map_err(|e| format!("Realtime connection failed: {}", e))?;
+    // Short reads, so heartbeats and settings checks run between messages.
+    let timeout = Some(Duration::from_secs(1));
+    match socket.get_mut() {
+        MaybeTlsStream::Plain(stream) => stream.set_read_timeout(timeout),
+        MaybeTlsStream::NativeTls(stream) => stream.get_ref().set_read_timeout(timeout),
+        _ => Ok(()),
+    }
+    .map_err(|e| e.to_string())?;
+
+    let topic = format!("realtime:syncone-{}", bucket);
+    send(
+        &mut socket,
+        json!({
+            "topic": topic,
+            "event": "phx_join",
+            "payload": {
+                "config": {
+                    "postgres_changes": [{
+                        "event": "*",
+                        "schema": "storage",
+                        "table": "objects",
+                        "filter": format!("bucket_id=eq.{}", bucket),
+                    }]
+                },
+                "access_token": key,
+            },
+            "ref": "1",
+        }),
+    )?;
+
+    let mut next_ref = 2u64;
+    let mut last_heartbeat = Instant::now();
+    let mut last_config_check = Instant::now();
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let Ok(message) = serde_json::from_str::<Value>(&text) else { continue };
+                let payload = &message["payload"];
+                match message["event"].as_str().unwrap_or("") {
+                    "phx_reply" if payload["status"] != "ok" => {
+                        return Err(format!("Realtime refused the subscription: {}", payload["response"]));
+                    }
+                    // Sent once the database side of the subscription is set up (or failed to be).
+                    "system" if payload["extension"] == "postgres_changes" => {
+                        if payload["status"] != "ok" {
+                            return Err(format!(
+                                "Realtime cannot watch the bucket: {} (is storage.objects in the supabase_realtime publication?)",
+                                payload["message"].as_str().unwrap_or("unknown error")
+                            ));
+                        }
+                        tracing::info!(bucket, "watching Supabase bucket via Realtime");
+                        set_active(true, emit);
+                    }
+                    "postgres_changes" => {
+                        let data = &payload["data"];
+                        if data["type"] == "DELETE" {
+                            continue;
+                        }
+                        if let Some(event) = data["record"]["name"].as_str().and_then(event_for) {
+                            emit(event);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Message::Close(_)) => return Err("Realtime closed the connection".to_string()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+            Err(e) => return Err(format!("Realtime connection lost: {}", e)),
+        }
+        if last_heartbeat.elapsed() >= Duration::from_secs(REALTIME_HEARTBEAT_SECS) {
+            send(
+                &mut socket,
+                json!({ "topic": "phoenix", "event": "heartbeat", "payload": {}, "ref": next_ref.to_string() }),
+            )?;
+            next_ref += 1;
+            last_heartbeat = Instant::now();
+        }
+        if last_config_check.elapsed() >= Duration::from_secs(CONFIG_CHECK_SECS) {
+            if !still_watching(watching) {
+                let _ = socket.close(None);
+                return Ok(());
+            }
+            last_config_check = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::watched_object;
+    use std::path::Path;
+
+    #[test]
+    fn names_objects_relative_to_the_cloud_folder() {
+        let dir = Path::new("/cloud/SyncONE");
+        assert_eq!(watched_object(dir, &dir.join("Save.meta.json")).as_deref(), Some("Save.meta.json"));
+        assert_eq!(
+            watched_object(dir, &dir.join("mod_profiles").join("modded").join("Mods.meta.json")).as_deref(),
+            Some("mod_profiles/modded/Mods.meta.json")
+        );
+        assert_eq!(watched_object(dir, &dir.join("host.json")).as_deref(), Some("host.json"));
+        assert_eq!(watched_object(dir, &dir.join("Mods").join("Mods").join("a.dll")), None);
+        assert_eq!(watched_object(dir, Path::new("/elsewhere/Save.meta.json")), None);
+    }
+}
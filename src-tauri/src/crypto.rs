@@ -0,0 +1,176 @@
+//! Optional end-to-end encryption of uploaded archives with a group passphrase.
+//!
+//! Layout of an encrypted object: `MAGIC | salt (16) | nonce (24) | ciphertext+tag`.
+//! The key is derived per object with Argon2id from the passphrase and the random salt, and
+//! the data is sealed with XChaCha20-Poly1305. The passphrase itself is kept in the OS
+//! credential store (Windows Credential Manager, macOS Keychain, Secret Service on Linux),
+//! never in `syncone_config.json`.
+
+use crate::sync::SyncConfig;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+const MAGIC: &[u8] = b"SYNCONE-ENC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEYRING_SERVICE: &str = "Syncone";
+const KEYRING_USER: &str = "group-passphrase";
+
+fn keyring_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| e.to_string())
+}
+
+/// Store (or with `None`/empty, remove) the group passphrase in the OS credential store.
+pub fn set_group_passphrase(passphrase: Option<&str>) -> Result<(), String> {
+    let entry = keyring_entry()?;
+    match passphrase.filter(|p| !p.is_empty()) {
+        Some(p) => entry.set_password(p).map_err(|e| e.to_string()),
+        None => match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        },
+    }
+}
+
+fn group_passphrase() -> Result<Option<String>, String> {
+    match keyring_entry()?.get_password() {
+        Ok(p) => Ok(Some(p)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Could not read group passphrase: {}", e)),
+    }
+}
+
+pub fn has_group_passphrase() -> bool {
+    matches!(group_passphrase(), Ok(Some(_)))
+}
+
+pub(crate) fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+pub(crate) fn encrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let key = derive_key(passphrase, &salt)?;
+    let cipher = XChaCha20Poly1305::new((&key).into());
+    let ciphertext = cipher
+        .encrypt(&nonce, data)
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Decrypt an object produced by `encrypt`. `name` is only used in error messages.
+pub(crate) fn decrypt(data: &[u8], passphrase: &str, name: &str) -> Result<Vec<u8>, String> {
+    let header_len = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if !is_encrypted(data) || data.len() < header_len {
+        return Err(format!("{} is not a valid encrypted archive", name));
+    }
+    let salt = &data[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = &data[MAGIC.len() + SALT_LEN..header_len];
+    let key = derive_key(passphrase, salt)?;
+    let cipher = XChaCha20Poly1305::new((&key).into());
+    cipher
+        .decrypt(XNonce::from_slice(nonce), &data[header_len..])
+        .map_err(|_| format!("Wrong group passphrase: could not decrypt {} (or the file was tampered with).", name))
+}
+
+/// Encrypt `data` before upload if encryption is enabled in config.
+pub(crate) fn seal_for_upload(config: &SyncConfig, data: Vec<u8>) -> Result<Vec<u8>, String> {
+    if !config.encrypt_archives {
+        return Ok(data);
+    }
+    let passphrase = group_passphrase()?
+        .ok_or("Encryption is enabled but no group passphrase is set on this PC.")?;
+    encrypt(&data, &passphrase)
+}
+
+/// Reverse `seal_for_upload` after download. Plain archives are refused while encryption is enabled.
+pub(crate) fn open_downloaded(config: &SyncConfig, data: Vec<u8>, name: &str) -> Result<Vec<u8>, String> {
+    if !is_encrypted(&data) {
+        if config.encrypt_archives {
+            return Err(format!(
+                "{} in the cloud is not encrypted, but encryption is enabled. Upload once with encryption on, or turn it off.",
+                name
+            ));
+        }
+        return Ok(data);
+    }
+    let passphrase = group_passphrase()?
+        .ok_or_else(|| format!("{} is encrypted. Enter the group passphrase in settings to fetch it.", name))?;
+    decrypt(&data, &passphrase, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decrypt, encrypt, is_encrypted, open_downloaded, MAGIC, NONCE_LEN, SALT_LEN};
+    use crate::sync::SyncConfig;
+
+    #[test]
+    fn round_trip() {
+        let sealed = encrypt(b"save data", "group secret").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert_eq!(sealed.len(), MAGIC.len() + SALT_LEN + NONCE_LEN + b"save data".len() + 16);
+        assert_eq!(decrypt(&sealed, "group secret", "Save.zip").unwrap(), b"save data");
+        // Fresh salt and nonce per object.
+        assert_ne!(encrypt(b"save data", "group secret").unwrap(), sealed);
+        assert_eq!(decrypt(&encrypt(b"", "group secret").unwrap(), "group secret", "empty").unwrap(), b"");
+    }
+
+    #[test]
+    fn wrong_passphrase_or_tampering_is_refused() {
+        let sealed = encrypt(b"save data", "group secret").unwrap();
+        let err = decrypt(&sealed, "guess", "Save.zip").unwrap_err();
+        assert!(err.starts_with("Wrong group passphrase: could not decrypt Save.zip"), "{}", err);
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&tampered, "group secret", "Save.zip").is_err());
+        let mut other_salt = sealed;
+        other_salt[MAGIC.len()] ^= 1;
+        assert!(decrypt(&other_salt, "group secret", "Save.zip").is_err());
+    }
+
+    #[test]
+    fn truncated_or_foreign_data_is_refused() {
+        let sealed = encrypt(b"save data", "group secret").unwrap();
+        let header_len = MAGIC.len() + SALT_LEN + NONCE_LEN;
+        for len in [0, MAGIC.len(), header_len - 1] {
+            let err = decrypt(&sealed[..len], "group secret", "Save.zip").unwrap_err();
+            assert_eq!(err, "Save.zip is not a valid encrypted archive");
+        }
+        // Header intact, tag cut short.
+        assert!(decrypt(&sealed[..header_len + 4], "group secret", "Save.zip").is_err());
+        let mut bad_magic = sealed;
+        bad_magic[0] = b'X';
+        assert!(!is_encrypted(&bad_magic));
+        assert_eq!(
+            decrypt(&bad_magic, "group secret", "Save.zip").unwrap_err(),
+            "Save.zip is not a valid encrypted archive"
+        );
+        assert!(decrypt(b"PK\x03\x04plain zip", "group secret", "Save.zip").is_err());
+    }
+
+    #[test]
+    fn plain_downloads_are_refused_only_while_encryption_is_on() {
+        let plain = b"PK\x03\x04plain zip".to_vec();
+        assert_eq!(open_downloaded(&SyncConfig::default(), plain.clone(), "Save.zip").unwrap(), plain);
+        let encrypting = SyncConfig { encrypt_archives: true, ..Default::default() };
+        let err = open_downloaded(&encrypting, plain, "Save.zip").unwrap_err();
+        assert!(err.contains("is not encrypted"), "{}", err);
+    }
+}
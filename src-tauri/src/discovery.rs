@@ -0,0 +1,254 @@
+//! Find Schedule I's save folder and install folder so users don't have to paste paths.
+//!
+//! Saves live in `LocalLow\TVGS\Schedule I\Saves\<SteamID>` – on Windows under the user profile,
+//! on Linux inside the Proton prefix (`steamapps/compatdata/<appid>/pfx`). The install folder is
+//! `steamapps/common/<installdir>` in whichever Steam library listed in `libraryfolders.vdf` has
+//! the game's `appmanifest_<appid>.acf`, which also holds the installed build ID.
+
+use crate::vdf::Vdf;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Steam app id of Schedule I (also the name of its Proton prefix).
+pub(crate) const SCHEDULE_I_APP_ID: &str = "3164500";
+const INSTALL_DIR_NAME: &str = "Schedule I";
+/// Save root below a Windows user profile (or a Proton prefix's `steamuser`).
+const SAVES_UNDER_PROFILE: [&str; 5] = ["AppData", "LocalLow", "TVGS", "Schedule I", "Saves"];
+
+/// One save slot (`SaveGame_N`) in a save folder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveSlot {
+    pub folder: String,
+    /// Organisation name from the slot's Game.json.
+    pub name: Option<String>,
+    /// Newest file mtime in the slot (unix seconds), i.e. roughly when it was last played.
+    pub last_played: Option<i64>,
+}
+
+/// A `Saves/<SteamID>` folder – what the save path setting should point at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveCandidate {
+    pub path: String,
+    pub steam_id: String,
+    /// "Windows" or "Proton".
+    pub source: String,
+    pub slots: Vec<SaveSlot>,
+    /// Newest `last_played` of its slots.
+    pub last_played: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameCandidate {
+    pub path: String,
+    /// The Steam library it was found in.
+    pub library: String,
+    /// Steam build ID of the installed game, from its app manifest.
+    pub build_id: Option<String>,
+}
+
+/// What Steam's `appmanifest_<appid>.acf` says about the installed game.
+#[derive(Debug, Clone)]
+pub(crate) struct AppManifest {
+    pub install_dir: PathBuf,
+    pub build_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveredPaths {
+    /// Most recently played first.
+    pub saves: Vec<SaveCandidate>,
+    pub games: Vec<GameCandidate>,
+}
+
+fn unix(t: SystemTime) -> Option<i64> {
+    t.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs() as i64)
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).map(PathBuf::from)
+}
+
+/// Steam install folders on this PC (those that exist).
+fn steam_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        // `reg query` prints "    SteamPath    REG_SZ    c:/program files (x86)/steam".
+        if let Ok(out) = std::process::Command::new("reg")
+            .args(["query", r"HKCU\Software\Valve\Steam", "/v", "SteamPath"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+        {
+            let text = String::from_utf8_lossy(&out.stdout);
+            if let Some(path) = text.lines().find_map(|l| l.split("REG_SZ").nth(1)) {
+                roots.push(PathBuf::from(path.trim()));
+            }
+        }
+        for var in ["ProgramFiles(x86)", "ProgramFiles"] {
+            if let Some(dir) = std::env::var_os(var) {
+                roots.push(Path::new(&dir).join("Steam"));
+            }
+        }
+    }
+    #[cfg(not(windows))]
+    {
+        if let Some(home) = home_dir() {
+            roots.push(home.join(".steam/steam"));
+            roots.push(home.join(".local/share/Steam"));
+            roots.push(home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"));
+        }
+    }
+    dedup_existing(roots)
+}
+
+/// Keep existing folders, dropping duplicates (e.g. `~/.steam/steam` is usually a symlink).
+fn dedup_existing(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen = Vec::new();
+    let mut out = Vec::new();
+    for path in paths {
+        if let Ok(canonical) = fs::canonicalize(&path) {
+            if canonical.is_dir() && !seen.contains(&canonical) {
+                seen.push(canonical);
+                out.push(path);
+            }
+        }
+    }
+    out
+}
+
+/// Library folders listed in `<steam root>/steamapps/libraryfolders.vdf` (plus the root itself).
+fn steam_libraries(root: &Path) -> Vec<PathBuf> {
+    let mut libraries = vec![root.to_path_buf()];
+    if let Ok(vdf) = crate::vdf::read(&root.join("steamapps").join("libraryfolders.vdf")) {
+        // Current Steam: "0" { "path" "D:\\SteamLibrary" ... }; older versions: "1" "D:\\SteamLibrary".
+        let folders = vdf.get("libraryfolders");
+        for (key, entry) in folders.into_iter().flat_map(|f| f.entries()) {
+            let path = match entry {
+                Vdf::Block(_) => entry.str_at(&["path"]),
+                Vdf::Str(path) if key.chars().all(|c| c.is_ascii_digit()) => Some(path.as_str()),
+                Vdf::Str(_) => None,
+            };
+            libraries.extend(path.map(PathBuf::from));
+        }
+    }
+    dedup_existing(libraries)
+}
+
+/// Schedule I's app manifest in a Steam library, if the game is installed there.
+pub(crate) fn app_manifest(library: &Path) -> Option<AppManifest> {
+    let steamapps = library.join("steamapps");
+    let acf = crate::vdf::read(&steamapps.join(format!("appmanifest_{}.acf", SCHEDULE_I_APP_ID))).ok()?;
+    let install_dir = acf.str_at(&["AppState", "installdir"]).unwrap_or(INSTALL_DIR_NAME);
+    Some(AppManifest {
+        install_dir: steamapps.join("common").join(install_dir),
+        build_id: acf.str_at(&["AppState", "buildid"]).map(str::to_string),
+    })
+}
+
+/// Build ID of the game this PC plays: the install under the configured game folder, else the
+/// first Steam library that has the game.
+pub(crate) fn installed_build(config: &crate::sync::SyncConfig) -> Option<String> {
+    // <library>/steamapps/common/<installdir>
+    let configured = config
+        .game_root
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .and_then(|root| Path::new(root).ancestors().nth(3).map(Path::to_path_buf));
+    match configured {
+        Some(library) => app_manifest(&library)?.build_id,
+        None => {
+            let libraries = steam_roots().iter().flat_map(|root| steam_libraries(root)).collect::<Vec<_>>();
+            libraries.iter().find_map(|library| app_manifest(library)?.build_id)
+        }
+    }
+}
+
+/// Organisation name stored in a slot's Game.json.
+fn slot_name(slot: &Path) -> Option<String> {
+    let bytes = fs::read(slot.join("Game.json")).ok()?;
+    let value: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+    value.get("OrganisationName")?.as_str().map(str::to_string)
+}
+
+/// Save slots of one `Saves/<SteamID>` folder, most recently played first.
+fn save_slots(dir: &Path) -> Vec<SaveSlot> {
+    let mut slots: Vec<SaveSlot> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().is_dir() && e.file_name().to_string_lossy().starts_with("SaveGame_"))
+                .map(|e| SaveSlot {
+                    folder: e.file_name().to_string_lossy().to_string(),
+                    name: slot_name(&e.path()),
+                    last_played: crate::sync::get_latest_mtime_recursive(&e.path()).ok().and_then(unix),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    slots.sort_by_key(|s| std::cmp::Reverse(s.last_played));
+    slots
+}
+
+/// Every `<SteamID>` folder under a `Saves` root.
+fn save_candidates(saves_root: &Path, source: &str) -> Vec<SaveCandidate> {
+    let Ok(entries) = fs::read_dir(saves_root) else { return Vec::new() };
+    entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| {
+            let slots = save_slots(&e.path());
+            SaveCandidate {
+                path: e.path().to_string_lossy().to_string(),
+                steam_id: e.file_name().to_string_lossy().to_string(),
+                source: source.to_string(),
+                last_played: slots.iter().filter_map(|s| s.last_played).max(),
+                slots,
+            }
+        })
+        .collect()
+}
+
+/// Look for save folders and game installs in the usual places.
+pub fn discover_paths() -> DiscoveredPaths {
+    let mut saves = Vec::new();
+    if cfg!(windows) {
+        if let Some(home) = home_dir() {
+            let root = SAVES_UNDER_PROFILE.iter().fold(home, |p, c| p.join(c));
+            saves.extend(save_candidates(&root, "Windows"));
+        }
+    }
+
+    let mut games = Vec::new();
+    let libraries = dedup_existing(steam_roots().iter().flat_map(|root| steam_libraries(root)).collect());
+    for library in libraries {
+        let steamapps = library.join("steamapps");
+        let manifest = app_manifest(&library);
+        let install = match &manifest {
+            Some(m) => m.install_dir.clone(),
+            None => steamapps.join("common").join(INSTALL_DIR_NAME),
+        };
+        if install.is_dir() {
+            games.push(GameCandidate {
+                path: install.to_string_lossy().to_string(),
+                library: library.to_string_lossy().to_string(),
+                build_id: manifest.and_then(|m| m.build_id),
+            });
+        }
+        if !cfg!(windows) {
+            let profile = steamapps
+                .join("compatdata")
+                .join(SCHEDULE_I_APP_ID)
+                .join("pfx/drive_c/users/steamuser");
+            let root = SAVES_UNDER_PROFILE.iter().fold(profile, |p, c| p.join(c));
+            saves.extend(save_candidates(&root, "Proton"));
+        }
+    }
+
+    saves.sort_by_key(|s| std::cmp::Reverse(s.last_played));
+    DiscoveredPaths { saves, games }
+}
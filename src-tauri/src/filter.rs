@@ -0,0 +1,320 @@
+//! Which files under a synced folder take part in sync.
+//!
+//! With a game root set, the mods target is the game folder restricted to the mod loader's
+//! subfolders (Mods, Plugins, UserLibs, UserData); everything else next to the game executable is
+//! never uploaded, overwritten or deleted. Per-target include/exclude glob patterns then narrow
+//! that down, e.g. to leave out logs, crash dumps and per-user settings.
+//!
+//! The same filter is used for mtimes, zipping, copying and the clear-before-extract step, so a
+//! file it leaves out is neither uploaded nor touched locally on pull.
+
+/// MelonLoader folders synced as part of the mods target when a game root is set.
+pub(crate) const DEFAULT_MOD_FOLDERS: [&str; 4] = ["Mods", "Plugins", "UserLibs", "UserData"];
+
+/// Left out of the mods target unless the user configures their own excludes.
+pub(crate) const DEFAULT_MOD_EXCLUDES: [&str; 7] = [
+    "*.log",
+    "*.tmp",
+    "*.bak",
+    "*.dmp",
+    "Logs",
+    "Cache",
+    "MelonPreferences.cfg",
+];
+
+/// Left out of the save target unless the user configures their own excludes.
+pub(crate) const DEFAULT_SAVE_EXCLUDES: [&str; 3] = ["*.tmp", "*.bak", "*.log"];
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PathFilter {
+    /// Top-level folders to sync; empty means everything under the root.
+    folders: Vec<String>,
+    /// Glob patterns; when not empty, only files matching one of them are synced.
+    include: Vec<String>,
+    /// Glob patterns for files or folders to leave out; they win over `include`.
+    exclude: Vec<String>,
+    /// Paths relative to the root that belong to this PC only (e.g. a mod's keybind config).
+    /// Like excludes, but always on top of them and matched exactly, not as globs.
+    local_only: Vec<String>,
+}
+
+impl PathFilter {
+    /// Everything under the root.
+    pub(crate) fn all() -> PathFilter {
+        PathFilter::default()
+    }
+
+    pub(crate) fn new(folders: Vec<String>, include: Vec<String>, exclude: Vec<String>) -> PathFilter {
+        let non_empty = |v: Vec<String>| v.into_iter().filter(|p| !p.trim().is_empty()).collect();
+        PathFilter {
+            folders: non_empty(folders),
+            include: non_empty(include),
+            exclude: non_empty(exclude),
+            local_only: Vec::new(),
+        }
+    }
+
+    /// Never upload, overwrite or delete `paths` (and everything below them).
+    pub(crate) fn with_local_only(mut self, paths: &[String]) -> PathFilter {
+        self.local_only = paths
+            .iter()
+            .map(|p| p.trim().replace('\\', "/").trim_matches('/').to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+        self
+    }
+
+    fn is_local_only(&self, rel: &str) -> bool {
+        self.local_only.iter().any(|p| {
+            rel.len() >= p.len()
+                && rel.is_char_boundary(p.len())
+                && rel[..p.len()].eq_ignore_ascii_case(p)
+                && (rel.len() == p.len() || rel.as_bytes()[p.len()] == b'/')
+        })
+    }
+
+    fn in_folders(&self, rel: &str) -> bool {
+        let first = rel.split('/').next().unwrap_or("");
+        self.folders.is_empty() || self.folders.iter().any(|f| f.trim().eq_ignore_ascii_case(first))
+    }
+
+    /// Whether the file at `rel` (relative to the root, `/`-separated) is synced.
+    pub(crate) fn matches(&self, rel: &str) -> bool {
+        self.in_folders(rel)
+            && (self.include.is_empty() || any_pattern_matches(&self.include, rel))
+            && !any_pattern_matches(&self.exclude, rel)
+            && !self.is_local_only(rel)
+    }
+
+    /// Whether the folder at `rel` may contain synced files (used to prune directory walks).
+    /// Includes are only checked per file, since e.g. `*.dll` can match anywhere below.
+    pub(crate) fn enters(&self, rel: &str) -> bool {
+        self.in_folders(rel) && !any_pattern_matches(&self.exclude, rel) && !self.is_local_only(rel)
+    }
+
+    /// Whether the root folder itself is synced (no folder restriction).
+    pub(crate) fn covers_root(&self) -> bool {
+        self.folders.is_empty()
+    }
+
+    /// Whether an empty folder at `rel` may be removed: inside a synced folder, not the folder itself.
+    pub(crate) fn may_remove_dir(&self, rel: &str) -> bool {
+        self.enters(rel) && (self.covers_root() || rel.contains('/'))
+    }
+
+    /// Refuse a cloud copy whose files lie outside the synced folders: it was uploaded with a
+    /// different mods layout, and applying it would clear our folders without replacing them.
+    pub(crate) fn check_layout<'a>(&self, name: &str, paths: impl IntoIterator<Item = &'a str>) -> Result<(), String> {
+        if self.folders.is_empty() {
+            return Ok(());
+        }
+        match paths.into_iter().find(|p| !self.in_folders(p)) {
+            Some(stray) => Err(format!(
+                "The cloud copy of {} has files outside {} (e.g. {}). It was probably uploaded without a game folder set – everyone in the group must use the same mods setup.",
+                name,
+                self.folders.join(", "),
+                stray
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A pattern without `/` matches any single path component (e.g. `*.log`, `Cache`); with `/` it
+/// matches from the root (e.g. `UserData/Backups/**`). Either way, matching a folder also matches
+/// everything below it. `*` and `?` stay within a component, `**` spans several.
+fn any_pattern_matches(patterns: &[String], rel: &str) -> bool {
+    let parts: Vec<&str> = rel.split('/').collect();
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.trim().trim_matches('/');
+        if pattern.contains('/') {
+            let pat: Vec<&str> = pattern.split('/').collect();
+            (1..=parts.len()).any(|n| glob_components(&pat, &parts[..n]))
+        } else {
+            parts.iter().any(|part| glob_component(pattern, part))
+        }
+    })
+}
+
+/// Match path components against pattern components, `**` matching zero or more of them.
+fn glob_components(pattern: &[&str], parts: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => parts.is_empty(),
+        Some((&"**", rest)) => (0..=parts.len()).any(|skip| glob_components(rest, &parts[skip..])),
+        Some((first, rest)) => {
+            !parts.is_empty() && glob_component(first, parts[0]) && glob_components(rest, &parts[1..])
+        }
+    }
+}
+
+/// Match one path component against `*` / `?` wildcards, ignoring ASCII case (Windows paths).
+fn glob_component(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.to_ascii_lowercase().chars().collect();
+    let t: Vec<char> = text.to_ascii_lowercase().chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathFilter;
+    use crate::sync::{clear_matching, mirror_dir, replace_dir_with};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn strings(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn excluding(patterns: &[&str]) -> PathFilter {
+        PathFilter::new(Vec::new(), Vec::new(), strings(patterns))
+    }
+
+    #[test]
+    fn pattern_without_slash_matches_any_component() {
+        let f = excluding(&["*.log", "Cache"]);
+        assert!(!f.matches("latest.log"));
+        assert!(!f.matches("Mods/Deep/crash.log"));
+        assert!(!f.matches("UserData/Cache/thumb.png"));
+        assert!(!f.enters("UserData/Cache"));
+        assert!(f.matches("Mods/latest.log.txt"));
+        assert!(f.matches("Mods/CacheTool.dll"));
+    }
+
+    #[test]
+    fn pattern_with_slash_is_anchored_at_the_root() {
+        let f = excluding(&["UserData/Backups/**", "UserData/*.cfg"]);
+        assert!(!f.matches("UserData/Backups/2024/save.json"));
+        assert!(f.matches("Mods/UserData/Backups/save.json"));
+        assert!(!f.matches("UserData/Keys.cfg"));
+        // `*` stays within one component.
+        assert!(f.matches("UserData/Sub/Keys.cfg"));
+        // A leading or trailing slash changes nothing.
+        assert!(!excluding(&["/UserData/Backups/"]).matches("UserData/Backups/a.json"));
+    }
+
+    #[test]
+    fn double_star_spans_zero_or_more_components() {
+        let f = PathFilter::new(Vec::new(), strings(&["**/*.dll"]), Vec::new());
+        assert!(f.matches("Loader.dll"));
+        assert!(f.matches("Mods/Loader.dll"));
+        assert!(f.matches("Mods/a/b/Loader.dll"));
+        assert!(!f.matches("Mods/readme.txt"));
+        let f = excluding(&["Mods/**/Old"]);
+        assert!(!f.matches("Mods/Old/a.dll"));
+        assert!(!f.matches("Mods/x/y/Old/a.dll"));
+        assert!(f.matches("Mods/Older/a.dll"));
+    }
+
+    #[test]
+    fn wildcards_and_case() {
+        let f = excluding(&["save_?.json", "*.TMP"]);
+        assert!(!f.matches("save_1.json"));
+        assert!(f.matches("save_10.json"));
+        assert!(!f.matches("Game.tmp"));
+        assert!(!f.matches("SAVE_2.JSON"));
+        let f = PathFilter::new(strings(&["Mods"]), Vec::new(), Vec::new());
+        assert!(f.matches("mods/a.dll"));
+        assert!(!f.matches("Plugins/a.dll"));
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let f = PathFilter::new(Vec::new(), strings(&["*.json"]), strings(&["Backups"]));
+        assert!(f.matches("Game.json"));
+        assert!(!f.matches("Backups/Game.json"));
+        assert!(!f.matches("Game.dll"));
+        // Includes are checked per file, so folders are still entered.
+        assert!(f.enters("Mods"));
+    }
+
+    fn tree(test: &str, side: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("syncone_filter_{}_{}", test, std::process::id())).join(side);
+        let _ = fs::remove_dir_all(&dir);
+        for (path, contents) in files {
+            fs::create_dir_all(dir.join(path).parent().unwrap()).unwrap();
+            fs::write(dir.join(path), contents).unwrap();
+        }
+        dir
+    }
+
+    fn read(dir: &Path, path: &str) -> Option<String> {
+        fs::read_to_string(dir.join(path)).ok()
+    }
+
+    fn keybinds() -> PathFilter {
+        PathFilter::all().with_local_only(&strings(&["UserData\\Keybinds", "Mods/Local.cfg"]))
+    }
+
+    #[test]
+    fn local_only_paths_are_matched_exactly() {
+        let f = keybinds();
+        assert!(!f.matches("UserData/Keybinds"));
+        assert!(!f.matches("userdata/keybinds/bindings.cfg"));
+        assert!(!f.enters("UserData/Keybinds"));
+        assert!(f.matches("UserData/KeybindsOld/bindings.cfg"));
+        assert!(f.matches("Mods/Local.cfg.bak"));
+        assert!(f.matches("Other/Mods/Local.cfg"));
+    }
+
+    #[test]
+    fn local_only_paths_are_kept_on_pull() {
+        let local = tree("pull", "local", &[
+            ("UserData/Keybinds/bindings.cfg", "mine"),
+            ("Mods/Local.cfg", "mine"),
+            ("Mods/Shared.dll", "old"),
+            ("Mods/Removed.dll", "old"),
+        ]);
+        let cloud = tree("pull", "cloud", &[
+            ("UserData/Keybinds/bindings.cfg", "theirs"),
+            ("Mods/Local.cfg", "theirs"),
+            ("Mods/Shared.dll", "new"),
+        ]);
+        replace_dir_with(&cloud, &local, &keybinds()).unwrap();
+        assert_eq!(read(&local, "UserData/Keybinds/bindings.cfg").as_deref(), Some("mine"));
+        assert_eq!(read(&local, "Mods/Local.cfg").as_deref(), Some("mine"));
+        assert_eq!(read(&local, "Mods/Shared.dll").as_deref(), Some("new"));
+        assert_eq!(read(&local, "Mods/Removed.dll"), None);
+
+        clear_matching(&local, &keybinds()).unwrap();
+        assert_eq!(read(&local, "UserData/Keybinds/bindings.cfg").as_deref(), Some("mine"));
+        assert_eq!(read(&local, "Mods/Local.cfg").as_deref(), Some("mine"));
+        assert_eq!(read(&local, "Mods/Shared.dll"), None);
+        let _ = fs::remove_dir_all(local.parent().unwrap());
+    }
+
+    #[test]
+    fn local_only_paths_are_never_uploaded() {
+        let local = tree("push", "local", &[
+            ("UserData/Keybinds/bindings.cfg", "mine"),
+            ("Mods/Local.cfg", "mine"),
+            ("Mods/Shared.dll", "code"),
+        ]);
+        let cloud = local.parent().unwrap().join("cloud");
+        mirror_dir(&local, &cloud, &keybinds()).unwrap();
+        assert_eq!(read(&cloud, "Mods/Shared.dll").as_deref(), Some("code"));
+        assert!(!cloud.join("UserData/Keybinds").exists());
+        assert!(!cloud.join("Mods/Local.cfg").exists());
+
+        let manifest = crate::manifest::build_manifest_filtered(&local, &keybinds()).unwrap();
+        let paths: Vec<&str> = manifest.files.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["Mods/Shared.dll"]);
+        let _ = fs::remove_dir_all(local.parent().unwrap());
+    }
+}
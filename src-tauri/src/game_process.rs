@@ -0,0 +1,162 @@
+//! Is Schedule I running? Pulling or restoring then overwrites files the game has open (and
+//! re-saves over later); pushing uploads a save that may be half-written. Sync refuses unless the
+//! user allows it (`Overrides::allow_game_running`).
+//!
+//! Linux reads `/proc` directly, which also sees the game under Proton/Wine (its path is in the
+//! command line). Windows asks `tasklist`, other systems `ps`.
+
+use crate::sync::SyncConfig;
+#[cfg(target_os = "linux")]
+use std::fs;
+#[cfg(target_os = "linux")]
+use std::path::Path;
+#[cfg(not(target_os = "linux"))]
+use std::process::Command;
+
+pub(crate) const DEFAULT_GAME_EXE: &str = "Schedule I.exe";
+
+/// Prefix of the error returned while the game runs; the UI offers to sync anyway.
+pub(crate) const GAME_RUNNING_PREFIX: &str = "GAME_RUNNING:";
+
+/// The executable to look for, or None if the check is turned off (empty name in config).
+fn game_exe(config: &SyncConfig) -> Option<&str> {
+    match config.game_exe.as_deref().map(str::trim) {
+        None => Some(DEFAULT_GAME_EXE),
+        Some("") => None,
+        Some(exe) => Some(exe),
+    }
+}
+
+/// Last component of a path with either separator (Wine command lines use `\`).
+#[cfg(not(windows))]
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Whether any process under `proc_root` (normally `/proc`) runs `exe`: by its `comm` name,
+/// which the kernel truncates to 15 bytes, or by an argument naming the executable.
+#[cfg(target_os = "linux")]
+pub(crate) fn running_in_proc(proc_root: &Path, exe: &str) -> bool {
+    let Ok(entries) = fs::read_dir(proc_root) else { return false };
+    let comm_name: String = exe.chars().take(15).collect();
+    entries.flatten().any(|entry| {
+        if !entry.file_name().to_string_lossy().bytes().all(|b| b.is_ascii_digit()) {
+            return false;
+        }
+        let dir = entry.path();
+        let comm_matches = fs::read_to_string(dir.join("comm"))
+            .is_ok_and(|comm| comm.trim_end().eq_ignore_ascii_case(&comm_name));
+        comm_matches
+            || fs::read(dir.join("cmdline")).is_ok_and(|cmdline| {
+                cmdline
+                    .split(|&b| b == 0)
+                    .any(|arg| file_name(&String::from_utf8_lossy(arg)).eq_ignore_ascii_case(exe))
+            })
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn is_running(exe: &str) -> bool {
+    running_in_proc(Path::new("/proc"), exe)
+}
+
+#[cfg(windows)]
+fn is_running(exe: &str) -> bool {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let filter = format!("IMAGENAME eq {}", exe);
+    Command::new("tasklist")
+        .args(["/FI", &filter, "/FO", "CSV", "/NH"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .is_ok_and(|out| {
+            String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .any(|line| line.trim_matches('"').to_lowercase().starts_with(&exe.to_lowercase()))
+        })
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn is_running(exe: &str) -> bool {
+    Command::new("ps")
+        .args(["-A", "-o", "comm="])
+        .output()
+        .is_ok_and(|out| {
+            String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .any(|line| file_name(line.trim()).eq_ignore_ascii_case(exe))
+        })
+}
+
+/// The configured game executable if it is running right now.
+pub(crate) fn running_game(config: &SyncConfig) -> Option<&str> {
+    game_exe(config).filter(|exe| is_running(exe))
+}
+
+/// Refuse to `action` ("fetch", "upload" or "restore") while the game runs.
+pub(crate) fn ensure_game_not_running(config: &SyncConfig, action: &str) -> Result<(), String> {
+    let Some(exe) = running_game(config) else { return Ok(()) };
+    let risk = match action {
+        "upload" => "Uploading now may send a save the game is still writing.",
+        "restore" => "Restoring now would overwrite files the game has open, and the game may save over them later.",
+        _ => "Fetching now would overwrite files the game has open, and the game may save over them later.",
+    };
+    Err(format!(
+        "{}Schedule I is running ({}).\n\n{}\n\nClose the game first, or {} anyway.",
+        GAME_RUNNING_PREFIX, exe, risk, action
+    ))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::running_in_proc;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// A fake `/proc` with one `<pid>/comm` and `<pid>/cmdline` per process.
+    fn fake_proc(test: &str, processes: &[(&str, &str, &[&str])]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("syncone_proc_{}_{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (pid, comm, args) in processes {
+            let dir = root.join(pid);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("comm"), format!("{}\n", comm)).unwrap();
+            let cmdline: Vec<u8> = args.iter().flat_map(|a| a.bytes().chain([0])).collect();
+            fs::write(dir.join("cmdline"), cmdline).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn matches_truncated_comm() {
+        let root = fake_proc("comm", &[("42", "Schedule I Demo", &[])]);
+        assert!(running_in_proc(&root, "Schedule I Demo.exe"));
+        assert!(running_in_proc(&root, "schedule i demo.EXE"));
+        assert!(!running_in_proc(&root, "Schedule I.exe"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn matches_wine_command_line() {
+        let root = fake_proc(
+            "wine",
+            &[
+                ("7", "bash", &["/bin/bash"]),
+                (
+                    "1234",
+                    "wine64-preload",
+                    &[r"Z:\home\me\.steam\steamapps\common\Schedule I\Schedule I.exe", "-batchmode"],
+                ),
+            ],
+        );
+        fs::create_dir_all(root.join("self")).unwrap();
+        assert!(running_in_proc(&root, "Schedule I.exe"));
+        assert!(!running_in_proc(&root, "Other.exe"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn missing_proc_is_not_running() {
+        assert!(!running_in_proc(&std::env::temp_dir().join("syncone_no_such_proc"), "Schedule I.exe"));
+    }
+}
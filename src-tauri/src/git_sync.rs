@@ -0,0 +1,569 @@
+//! Git backend: commit Save/ and Mods/ into a git repository, one commit per push.
+//!
+//! A working clone lives in the Syncone config dir (`git_work/`) and is pushed to
+//! `git_remote` (a local path or file:// URL, preferably a bare repo). History, blame
+//! and rollback then come from git itself.
+
+use crate::supabase_sync::max_lifetime_earnings_from_save_dir;
+use crate::sync::{
+    config_dir, inject_has_exited_rv, latest_mtime, local_target, mirror_dir, replace_dir_with,
+    set_synced_organisation_name, Overrides, SyncConfig, SyncStatus, SyncTarget,
+};
+use crate::filter::PathFilter;
+use crate::manifest::ManifestEntry;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_BRANCH: &str = "main";
+
+/// One commit touching the save, for the history list in the UI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveVersion {
+    pub commit: String,
+    pub timestamp: i64,
+    pub message: String,
+}
+
+pub(crate) fn use_git(config: &SyncConfig) -> bool {
+    config
+        .git_remote
+        .as_deref()
+        .is_some_and(|r| !r.trim().is_empty())
+}
+
+fn system_time_to_unix(t: SystemTime) -> Option<i64> {
+    t.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs() as i64)
+}
+
+fn branch(config: &SyncConfig) -> &str {
+    config
+        .git_branch
+        .as_deref()
+        .filter(|b| !b.trim().is_empty())
+        .unwrap_or(DEFAULT_BRANCH)
+}
+
+/// Run git in `dir` and return trimmed stdout, or stderr as error.
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    git_bytes(dir, args).map(|out| String::from_utf8_lossy(&out).trim().to_string())
+}
+
+/// Run git in `dir` and return stdout as is (file contents), or stderr as error.
+fn git_bytes(dir: &Path, args: &[&str]) -> Result<Vec<u8>, String> {
+    let started = Instant::now();
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Could not run git (is it installed?): {}", e))?;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        tracing::debug!(args = ?args, elapsed_ms, stderr = %stderr.trim(), "git failed");
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or(""),
+            stderr.trim()
+        ));
+    }
+    tracing::debug!(args = ?args, elapsed_ms, "git");
+    Ok(out.stdout)
+}
+
+fn work_dir() -> Result<PathBuf, String> {
+    Ok(config_dir().map_err(|e| e.to_string())?.join("git_work"))
+}
+
+/// Whether `work` is a clone of `remote`.
+fn is_clone_of(work: &Path, remote: &str) -> bool {
+    work.join(".git").exists() && git(work, &["remote", "get-url", "origin"]).ok().as_deref() == Some(remote)
+}
+
+/// Fetch into an existing clone. Returns whether `origin/<branch>` exists (false for an empty repo).
+fn fetch_origin(work: &Path, branch: &str) -> Result<bool, String> {
+    git(work, &["fetch", "--quiet", "--prune", "origin"])?;
+    let remote_ref = format!("refs/remotes/origin/{}", branch);
+    Ok(git(work, &["rev-parse", "--verify", "--quiet", &remote_ref]).is_ok())
+}
+
+/// Make sure the working clone exists, points at `remote` and has fresh refs.
+/// Returns the clone path and whether `origin/<branch>` exists yet (false for an empty repo).
+/// Only for syncs holding `sync::sync_lock`: it may delete and re-clone the work tree.
+fn prepare_work_clone(remote: &str, branch: &str) -> Result<(PathBuf, bool), String> {
+    let work = work_dir()?;
+    if !is_clone_of(&work, remote) {
+        if work.exists() {
+            fs::remove_dir_all(&work).map_err(|e| e.to_string())?;
+        }
+        let parent = work.parent().ok_or("Invalid git work dir")?;
+        let out = Command::new("git")
+            .arg("-C")
+            .arg(parent)
+            .args(["clone", "--quiet", remote])
+            .arg(&work)
+            .output()
+            .map_err(|e| format!("Could not run git (is it installed?): {}", e))?;
+        if !out.status.success() {
+            return Err(format!("git clone failed: {}", String::from_utf8_lossy(&out.stderr).trim()));
+        }
+    }
+    let has_remote_branch = fetch_origin(&work, branch)?;
+    Ok((work, has_remote_branch))
+}
+
+/// The working clone with fresh refs, for reads (status, history, committed files). Reads only
+/// fetch and then use `log`, `show` and `ls-tree`, so they never disturb a sync working in the
+/// clone; a missing or re-pointed clone is only (re)made while no sync runs.
+fn fetched_clone(remote: &str, branch: &str) -> Result<(PathBuf, bool), String> {
+    let work = work_dir()?;
+    if is_clone_of(&work, remote) {
+        let has_remote_branch = fetch_origin(&work, branch)?;
+        return Ok((work, has_remote_branch));
+    }
+    let _running = crate::sync::sync_lock()?;
+    prepare_work_clone(remote, branch)
+}
+
+/// Check out `origin/<branch>` in the working clone, discarding local leftovers.
+fn checkout_remote_branch(work: &Path, branch: &str, has_remote_branch: bool) -> Result<(), String> {
+    if has_remote_branch {
+        let origin_branch = format!("origin/{}", branch);
+        git(work, &["checkout", "--quiet", "-B", branch, &origin_branch])?;
+        git(work, &["reset", "--quiet", "--hard", &origin_branch])?;
+        git(work, &["clean", "--quiet", "-fdx"])?;
+    } else {
+        // Empty repository: start the branch from scratch.
+        let head = format!("refs/heads/{}", branch);
+        git(work, &["symbolic-ref", "HEAD", &head])?;
+    }
+    Ok(())
+}
+
+/// Commit time of the last commit on `origin/<branch>` that touched `folder` (Save or Mods).
+fn last_commit_time(work: &Path, branch: &str, folder: &str) -> Option<i64> {
+    let origin_branch = format!("origin/{}", branch);
+    git(work, &["log", "-1", "--format=%ct", &origin_branch, "--", folder])
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+}
+
+/// Contents of `file` on the fetched branch tip, read from the object store without touching the
+/// work tree (a push may be staging files there).
+fn committed_file(work: &Path, branch: &str, file: &str) -> Option<Vec<u8>> {
+    git_bytes(work, &["show", &format!("origin/{}:{}", branch, file)]).ok()
+}
+
+/// The push record of `name` on the fetched branch tip.
+fn committed_push_meta(work: &Path, branch: &str, name: &str) -> Option<crate::push_meta::PushMeta> {
+    let object = crate::push_meta::meta_object(name);
+    let json = committed_file(work, branch, &object)?;
+    crate::push_meta::parse_push_meta(&object, &json).ok()
+}
+
+/// Write `<name>.manifest.json` and its detached signature for `work/<name>` and stage them.
+fn stage_signed_manifest(work: &Path, name: &str) -> Result<(), String> {
+    let (manifest_bytes, sig_bytes) = crate::signing::signed_manifest(name, &work.join(name), &PathFilter::all())?;
+    let manifest_file = format!("{}.manifest.json", name);
+    let sig_file = format!("{}.manifest.sig", name);
+    fs::write(work.join(&manifest_file), manifest_bytes).map_err(|e| e.to_string())?;
+    fs::write(work.join(&sig_file), sig_bytes).map_err(|e| e.to_string())?;
+    git(work, &["add", "--", &manifest_file, &sig_file])?;
+    Ok(())
+}
+
+/// Check `work/<name>` against the signed manifest committed next to it. Returns its file list.
+fn verify_checked_out(config: &SyncConfig, work: &Path, name: &str) -> Result<Vec<ManifestEntry>, String> {
+    let manifest = fs::read(work.join(format!("{}.manifest.json", name))).ok();
+    let signature = fs::read(work.join(format!("{}.manifest.sig", name))).ok();
+    let actual = crate::manifest::build_manifest(&work.join(name))?.files;
+    crate::signing::verify_download(config, name, manifest.as_deref(), signature.as_deref(), &actual)?;
+    Ok(actual)
+}
+
+/// Put `<name>.manifest.json`/`.sig` as of `commit` into the work tree (or remove the tip's
+/// copies if that version had none), so `verify_checked_out` checks the restored files.
+fn checkout_version_manifest(work: &Path, commit: &str, name: &str) -> Result<(), String> {
+    for file in [format!("{}.manifest.json", name), format!("{}.manifest.sig", name)] {
+        match git_bytes(work, &["show", &format!("{}:{}", commit, file)]) {
+            Ok(bytes) => fs::write(work.join(&file), bytes).map_err(|e| e.to_string())?,
+            Err(_) => {
+                let _ = fs::remove_file(work.join(&file));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn local_mtime(path: &Path, filter: &PathFilter) -> Option<i64> {
+    latest_mtime(path, filter).and_then(system_time_to_unix)
+}
+
+pub fn sync_pull_git(config: &SyncConfig, target: SyncTarget, overrides: Overrides) -> Result<String, String> {
+    let (local_save, save_filter) = local_target(config, "Save")?;
+    let (local_mods, mods_filter) = local_target(config, "Mods")?;
+    let remote = config.git_remote.as_ref().ok_or("Git repository is not set")?;
+    let branch = branch(config);
+
+    let local_save = local_save.as_path();
+    let local_mods = local_mods.as_path();
+    let mut messages = Vec::new();
+
+    let (work, has_remote_branch) = prepare_work_clone(remote, branch)?;
+    if !has_remote_branch {
+        return Ok("Nothing new to fetch – you already have the latest version.".to_string());
+    }
+    checkout_remote_branch(&work, branch, has_remote_branch)?;
+
+    if target == SyncTarget::Save || target == SyncTarget::Both {
+        let repo_save = work.join("Save");
+        if let Some(cloud_ts) = last_commit_time(&work, branch, "Save").filter(|_| repo_save.exists()) {
+            let local_ts = local_mtime(local_save, &save_filter);
+            let should_pull = match local_ts {
+                Some(local) => config.refetch_save || cloud_ts > local,
+                None => true,
+            };
+            crate::sync::log_pull_decision("Save", Some(cloud_ts), local_ts, should_pull);
+            if should_pull {
+                // Unless overridden, check if local save is more advanced than the repository version.
+                if !overrides.ignore_progress {
+                    let cloud_max = max_lifetime_earnings_from_save_dir(&repo_save);
+                    let local_max = max_lifetime_earnings_from_save_dir(local_save);
+                    if let (Some(local_val), Some(cloud_val)) = (local_max, cloud_max) {
+                        if local_val > cloud_val {
+                            return Err(crate::sync::fetch_progress_warning(local_val, cloud_val));
+                        }
+                    }
+                }
+                if !overrides.ignore_setup_mismatch {
+                    crate::save_meta::check_save_meta(config, target == SyncTarget::Both)?;
+                }
+                verify_checked_out(config, &work, "Save")?;
+                replace_dir_with(&repo_save, local_save, &save_filter).map_err(|e| e.to_string())?;
+                inject_has_exited_rv(local_save)?;
+                set_synced_organisation_name(local_save)?;
+                messages.push("Save fetched from git.");
+            }
+        }
+    }
+
+    if target == SyncTarget::Mods || target == SyncTarget::Both {
+        let mods_name = crate::profiles::cloud_name(config, "Mods");
+        let repo_mods = work.join(&mods_name);
+        if let Some(cloud_ts) = last_commit_time(&work, branch, &mods_name).filter(|_| repo_mods.exists()) {
+            let local_ts = local_mtime(local_mods, &mods_filter);
+            let should_pull = match local_ts {
+                Some(local) => config.refetch_mods || cloud_ts > local,
+                None => true,
+            };
+            crate::sync::log_pull_decision(&mods_name, Some(cloud_ts), local_ts, should_pull);
+            if should_pull {
+                let actual = verify_checked_out(config, &work, &mods_name)?;
+                mods_filter.check_layout(&mods_name, actual.iter().map(|e| e.path.as_str()))?;
+                replace_dir_with(&repo_mods, local_mods, &mods_filter).map_err(|e| e.to_string())?;
+                messages.push("Mods fetched from git.");
+            }
+        }
+    }
+
+    if messages.is_empty() {
+        Ok("Nothing new to fetch – you already have the latest version.".to_string())
+    } else {
+        Ok(messages.join(" "))
+    }
+}
+
+pub fn sync_push_git(config: &SyncConfig, target: SyncTarget, overrides: Overrides) -> Result<String, String> {
+    let (local_save, save_filter) = local_target(config, "Save")?;
+    let (local_mods, mods_filter) = local_target(config, "Mods")?;
+    let remote = config.git_remote.as_ref().ok_or("Git repository is not set")?;
+    let branch = branch(config);
+
+    if config.encrypt_archives {
+        return Err("Encryption is not supported by the git backend (it stores plain files for history). Turn it off or use Supabase / zip archives.".to_string());
+    }
+
+    let local_save = local_save.as_path();
+    let local_mods = local_mods.as_path();
+    let mut messages = Vec::new();
+    let mut commit_parts = Vec::new();
+    let mut pushed = Vec::new();
+    let mut found_local = false;
+
+    let (work, has_remote_branch) = prepare_work_clone(remote, branch)?;
+    checkout_remote_branch(&work, branch, has_remote_branch)?;
+
+    if (target == SyncTarget::Save || target == SyncTarget::Both) && local_save.exists() {
+        let repo_save = work.join("Save");
+        let local_max = max_lifetime_earnings_from_save_dir(local_save);
+        // Unless overridden, check LifetimeEarnings to prevent committing a save that is behind the repository.
+        if !overrides.ignore_progress {
+            let cloud_max = max_lifetime_earnings_from_save_dir(&repo_save);
+            if let (Some(local_val), Some(cloud_val)) = (local_max, cloud_max) {
+                if local_val < cloud_val {
+                    return Err(crate::sync::upload_progress_warning(local_val, cloud_val));
+                }
+            }
+        }
+
+        found_local = true;
+        mirror_dir(local_save, &repo_save, &save_filter).map_err(|e| e.to_string())?;
+        git(&work, &["add", "--all", "--", "Save"])?;
+        if !git(&work, &["status", "--porcelain", "--", "Save"])?.is_empty() {
+            // Stamp the save name so it's easy to recognize in-game. Only once the save is known
+            // to have changed: the stamp alone would otherwise make every push a new version.
+            let _ = set_synced_organisation_name(local_save);
+            mirror_dir(local_save, &repo_save, &save_filter).map_err(|e| e.to_string())?;
+            git(&work, &["add", "--all", "--", "Save"])?;
+            stage_signed_manifest(&work, "Save")?;
+            pushed.push(("Save".to_string(), local_save, &save_filter));
+            commit_parts.push(match local_max {
+                Some(v) => format!("save (lifetime earnings {:.0})", v),
+                None => "save".to_string(),
+            });
+            messages.push("Save committed to git.");
+        }
+    }
+    if (target == SyncTarget::Mods || target == SyncTarget::Both) && local_mods.exists() {
+        let mods_name = crate::profiles::cloud_name(config, "Mods");
+        let repo_mods = work.join(&mods_name);
+        found_local = true;
+        mirror_dir(local_mods, &repo_mods, &mods_filter).map_err(|e| e.to_string())?;
+        git(&work, &["add", "--all", "--", &mods_name])?;
+        if !git(&work, &["status", "--porcelain", "--", &mods_name])?.is_empty() {
+            stage_signed_manifest(&work, &mods_name)?;
+            let (inventory, inventory_sig) = crate::inventory::signed_inventory(&repo_mods, &PathFilter::all())?;
+            let inventory_file = crate::inventory::inventory_object(config);
+            let inventory_sig_file = crate::inventory::inventory_sig_object(config);
+            fs::write(work.join(&inventory_file), inventory).map_err(|e| e.to_string())?;
+            fs::write(work.join(&inventory_sig_file), inventory_sig).map_err(|e| e.to_string())?;
+            git(&work, &["add", "--", &inventory_file, &inventory_sig_file])?;
+            pushed.push((mods_name, local_mods, &mods_filter));
+            commit_parts.push("mods".to_string());
+            messages.push("Mods committed to git.");
+        }
+    }
+
+    if !found_local {
+        return Ok("No local folders to upload.".to_string());
+    }
+    // Judged by the folders alone: manifests and records carry timestamps and always differ.
+    if messages.is_empty() {
+        return Ok("Nothing changed since the last upload.".to_string());
+    }
+    for (name, local, filter) in pushed {
+        let meta_file = crate::push_meta::meta_object(&name);
+        let meta = crate::push_meta::push_meta_bytes(config, &name, local, filter)?;
+        fs::write(work.join(&meta_file), meta).map_err(|e| e.to_string())?;
+        git(&work, &["add", "--", &meta_file])?;
+    }
+
+    let uploader = crate::push_meta::uploader_name(config);
+    let mut message = format!("Push {} by {}", commit_parts.join(" and "), uploader);
+    if let Some(note) = config.push_note.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+        message = format!("{}: {}", message, note);
+    }
+    let author_name = format!("user.name={}", uploader);
+    let author_email = format!("user.email={}@syncone", uploader.replace(' ', "."));
+    git(
+        &work,
+        &["-c", &author_name, "-c", &author_email, "commit", "--quiet", "-m", &message],
+    )?;
+    let refspec = format!("HEAD:refs/heads/{}", branch);
+    git(&work, &["push", "--quiet", "origin", &refspec])?;
+    messages.push("Pushed to repository.");
+    Ok(messages.join(" "))
+}
+
+pub fn get_sync_status_git(config: &SyncConfig) -> SyncStatus {
+    let mut status = SyncStatus {
+        save_local_mtime: None,
+        save_cloud_mtime: None,
+        mods_local_mtime: None,
+        mods_cloud_mtime: None,
+        save_local_newer: false,
+        save_cloud_newer: false,
+        mods_local_newer: false,
+        mods_cloud_newer: false,
+        save_path_used: None,
+        mods_path_used: None,
+        save_last_push: None,
+        mods_last_push: None,
+    };
+    let ((save_path, save_filter), (mods_path, mods_filter), remote) = match (
+        local_target(config, "Save"),
+        local_target(config, "Mods"),
+        config.git_remote.as_deref(),
+    ) {
+        (Ok(s), Ok(m), Some(r)) => (s, m, r),
+        _ => return status,
+    };
+    status.save_path_used = Some(save_path.to_string_lossy().to_string());
+    status.mods_path_used = Some(mods_path.to_string_lossy().to_string());
+    status.save_local_mtime = local_mtime(&save_path, &save_filter);
+    status.mods_local_mtime = local_mtime(&mods_path, &mods_filter);
+
+    let branch = branch(config);
+    if let Ok((work, true)) = fetched_clone(remote, branch) {
+        let mods_name = crate::profiles::cloud_name(config, "Mods");
+        status.save_cloud_mtime = last_commit_time(&work, branch, "Save");
+        status.mods_cloud_mtime = last_commit_time(&work, branch, &mods_name);
+        status.save_last_push = committed_push_meta(&work, branch, "Save");
+        status.mods_last_push = committed_push_meta(&work, branch, &mods_name);
+    }
+
+    if let (Some(local), Some(cloud)) = (status.save_local_mtime, status.save_cloud_mtime) {
+        status.save_local_newer = local > cloud;
+        status.save_cloud_newer = cloud > local;
+    } else if status.save_local_mtime.is_some() && status.save_cloud_mtime.is_none() {
+        status.save_local_newer = true;
+    } else if status.save_cloud_mtime.is_some() && status.save_local_mtime.is_none() {
+        status.save_cloud_newer = true;
+    }
+    if let (Some(local), Some(cloud)) = (status.mods_local_mtime, status.mods_cloud_mtime) {
+        status.mods_local_newer = local > cloud;
+        status.mods_cloud_newer = cloud > local;
+    } else if status.mods_local_mtime.is_some() && status.mods_cloud_mtime.is_none() {
+        status.mods_local_newer = true;
+    } else if status.mods_cloud_mtime.is_some() && status.mods_local_mtime.is_none() {
+        status.mods_cloud_newer = true;
+    }
+    status
+}
+
+/// Contents of `file` (relative to the repository root) on the branch tip, if it exists.
+pub(crate) fn read_committed_file(config: &SyncConfig, file: &str) -> Result<Option<Vec<u8>>, String> {
+    let remote = config.git_remote.as_ref().ok_or("Git repository is not set")?;
+    let branch = branch(config);
+    let (work, has_remote_branch) = fetched_clone(remote, branch)?;
+    if !has_remote_branch {
+        return Ok(None);
+    }
+    Ok(committed_file(&work, branch, file))
+}
+
+/// Names of the folders directly inside `dir` on the branch tip (empty if there are none).
+pub(crate) fn committed_dirs(config: &SyncConfig, dir: &str) -> Result<Vec<String>, String> {
+    let remote = config.git_remote.as_ref().ok_or("Git repository is not set")?;
+    let branch = branch(config);
+    let (work, has_remote_branch) = fetched_clone(remote, branch)?;
+    if !has_remote_branch {
+        return Ok(Vec::new());
+    }
+    let tree = format!("origin/{}:{}", branch, dir);
+    let listing = git(&work, &["ls-tree", "-d", "--name-only", &tree]).unwrap_or_default();
+    Ok(listing.lines().map(str::to_string).collect())
+}
+
+/// Most recent commits that touched the save, newest first.
+pub fn save_history(config: &SyncConfig, limit: usize) -> Result<Vec<SaveVersion>, String> {
+    let remote = config.git_remote.as_ref().ok_or("Git repository is not set")?;
+    let branch = branch(config);
+    let (work, has_remote_branch) = fetched_clone(remote, branch)?;
+    if !has_remote_branch {
+        return Ok(Vec::new());
+    }
+    let origin_branch = format!("origin/{}", branch);
+    let max_count = format!("--max-count={}", limit);
+    let log = git(&work, &["log", &max_count, "--format=%H%x1f%ct%x1f%s", &origin_branch, "--", "Save"])?;
+    Ok(log
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\x1f');
+            let commit = parts.next()?.to_string();
+            let timestamp = parts.next()?.parse().ok()?;
+            let message = parts.next().unwrap_or_default().to_string();
+            Some(SaveVersion { commit, timestamp, message })
+        })
+        .collect())
+}
+
+/// Roll the local save folder back to the version stored in `commit`.
+/// Upload afterwards to make that version the newest one for everyone.
+pub fn restore_save_version(config: &SyncConfig, commit: &str, overrides: Overrides) -> Result<String, String> {
+    if !overrides.allow_game_running {
+        crate::game_process::ensure_game_not_running(config, "restore")?;
+    }
+    let (local_save, save_filter) = local_target(config, "Save")?;
+    let remote = config.git_remote.as_ref().ok_or("Git repository is not set")?;
+    let branch = branch(config);
+    let local_save = local_save.as_path();
+
+    let (work, has_remote_branch) = prepare_work_clone(remote, branch)?;
+    checkout_remote_branch(&work, branch, has_remote_branch)?;
+    git(&work, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", commit)])
+        .map_err(|_| format!("Unknown save version: {}", commit))?;
+
+    let repo_save = work.join("Save");
+    if repo_save.exists() {
+        fs::remove_dir_all(&repo_save).map_err(|e| e.to_string())?;
+    }
+    let restored = git(&work, &["checkout", commit, "--", "Save"])
+        .and_then(|_| checkout_version_manifest(&work, commit, "Save"))
+        .and_then(|_| verify_checked_out(config, &work, "Save"))
+        .and_then(|_| replace_dir_with(&repo_save, local_save, &save_filter).map_err(|e| e.to_string()))
+        .and_then(|_| inject_has_exited_rv(local_save));
+    // Put the working clone back on the branch tip whatever happened.
+    checkout_remote_branch(&work, branch, has_remote_branch)?;
+    restored?;
+    let short: String = commit.chars().take(8).collect();
+    Ok(format!("Save restored to version {}.", short))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{committed_file, fetch_origin, git, last_commit_time};
+    use std::fs;
+    use std::path::Path;
+
+    fn commit(work: &Path, message: &str) {
+        git(work, &["-c", "user.name=t", "-c", "user.email=t@t", "commit", "--quiet", "-m", message]).unwrap();
+    }
+
+    #[test]
+    fn status_reads_leave_a_push_in_progress_alone() {
+        let root = std::env::temp_dir().join(format!("syncone_git_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        git(&root, &["init", "--quiet", "--bare", "remote.git"]).unwrap();
+        let remote = root.join("remote.git").to_string_lossy().to_string();
+        git(&root, &["clone", "--quiet", &remote, "work"]).unwrap();
+        let work = root.join("work");
+        fs::create_dir_all(work.join("Save")).unwrap();
+        fs::write(work.join("Save/Game.json"), "old").unwrap();
+        git(&work, &["add", "--all"]).unwrap();
+        commit(&work, "first");
+        git(&work, &["push", "--quiet", "origin", "HEAD:refs/heads/main"]).unwrap();
+
+        // A push mid-way: one file mirrored and staged, one not yet added.
+        fs::write(work.join("Save/Game.json"), "new").unwrap();
+        git(&work, &["add", "Save/Game.json"]).unwrap();
+        fs::write(work.join("Save/Extra.json"), "extra").unwrap();
+        let staged = git(&work, &["status", "--porcelain"]).unwrap();
+
+        let reader_work = work.clone();
+        let reader = std::thread::spawn(move || {
+            for _ in 0..5 {
+                assert!(fetch_origin(&reader_work, "main").unwrap());
+                assert_eq!(committed_file(&reader_work, "main", "Save/Game.json").unwrap(), b"old");
+                assert!(last_commit_time(&reader_work, "main", "Save").is_some());
+            }
+        });
+        while !reader.is_finished() {
+            assert_eq!(git(&work, &["status", "--porcelain"]).unwrap(), staged);
+        }
+        reader.join().unwrap();
+        assert_eq!(git(&work, &["status", "--porcelain"]).unwrap(), staged);
+
+        git(&work, &["add", "--all"]).unwrap();
+        commit(&work, "second");
+        git(&work, &["push", "--quiet", "origin", "HEAD:refs/heads/main"]).unwrap();
+        assert!(fetch_origin(&work, "main").unwrap());
+        assert_eq!(committed_file(&work, "main", "Save/Game.json").unwrap(), b"new");
+        assert_eq!(committed_file(&work, "main", "Save/Extra.json").unwrap(), b"extra");
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,151 @@
+//! Host handoff: the host uploads the save and names the next host in the lease, `host.json`.
+//! That player's SyncONE offers to take over (see `cloud_watch`); accepting fetches the save and
+//! makes them the holder.
+//!
+//! The lease only records who hosts; it does not lock anything, so syncing works as before for
+//! everyone. Like presence it lives in a cloud folder or Supabase, not in git.
+
+use crate::sync::{sync_pull, sync_push, Overrides, SyncConfig, SyncTarget};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub(crate) const LEASE_OBJECT: &str = "host.json";
+/// How long before the handoff the handed-off save may have been pushed: its record is written
+/// just before the save's upload finishes, the lease right after.
+const HANDOFF_PUSH_SLACK_SECS: i64 = 120;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostLease {
+    /// Who hosts now; None between a handoff and its acceptance.
+    pub holder: Option<String>,
+    pub holder_hostname: Option<String>,
+    /// Unix seconds the lease last changed hands.
+    pub since: i64,
+    /// Named by the last handoff, until they accept.
+    pub next_host: Option<String>,
+    pub handoff_by: Option<String>,
+    pub handoff_at: Option<i64>,
+    /// The note entered with the handoff.
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn ensure_supported(config: &SyncConfig) -> Result<(), String> {
+    if crate::git_sync::use_git(config) {
+        return Err("Handoff needs a cloud folder or Supabase; the git backend has nowhere to keep the host lease.".to_string());
+    }
+    Ok(())
+}
+
+fn same_person(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+/// The current lease, if anyone has handed off yet.
+pub fn get_host_lease(config: &SyncConfig) -> Result<Option<HostLease>, String> {
+    ensure_supported(config)?;
+    let remote = crate::blobs::Remote::from_config(config)?;
+    let Some(data) = remote.get(LEASE_OBJECT)? else { return Ok(None) };
+    let data = crate::crypto::open_downloaded(config, data, LEASE_OBJECT)?;
+    serde_json::from_slice(&data)
+        .map(Some)
+        .map_err(|e| format!("{} is unreadable: {}", LEASE_OBJECT, e))
+}
+
+fn put_lease(config: &SyncConfig, lease: &HostLease) -> Result<(), String> {
+    let data = serde_json::to_vec_pretty(lease).map_err(|e| e.to_string())?;
+    let remote = crate::blobs::Remote::from_config(config)?;
+    remote.put(LEASE_OBJECT, &crate::crypto::seal_for_upload(config, data)?)
+}
+
+/// Whether `lease` names this user as the next host.
+pub(crate) fn offered_to_us(config: &SyncConfig, lease: &HostLease) -> bool {
+    lease
+        .next_host
+        .as_deref()
+        .is_some_and(|next| same_person(next, &crate::push_meta::uploader_name(config)))
+}
+
+/// Whether this user made the latest change to `lease` (took over, or handed off).
+pub(crate) fn changed_by_us(config: &SyncConfig, lease: &HostLease) -> bool {
+    let me = crate::push_meta::uploader_name(config);
+    match &lease.holder {
+        Some(holder) => same_person(holder, &me),
+        None => lease.handoff_by.as_deref().is_some_and(|by| same_person(by, &me)),
+    }
+}
+
+/// Upload the save, give up the lease and name `next_host` (their display name) as the next host.
+/// The upload's warnings can be overridden, as with a normal upload.
+pub fn handoff(config: &SyncConfig, next_host: &str, overrides: Overrides) -> Result<String, String> {
+    ensure_supported(config)?;
+    let next_host = next_host.trim();
+    if next_host.is_empty() {
+        return Err("Choose who hosts next.".to_string());
+    }
+    let me = crate::push_meta::uploader_name(config);
+    if same_person(next_host, &me) {
+        return Err("You can't hand off to yourself.".to_string());
+    }
+    let pushed = sync_push(config, SyncTarget::Save, overrides)?;
+    let at = now();
+    put_lease(
+        config,
+        &HostLease {
+            holder: None,
+            holder_hostname: None,
+            since: at,
+            next_host: Some(next_host.to_string()),
+            handoff_by: Some(me),
+            handoff_at: Some(at),
+            note: config.push_note.as_deref().map(str::trim).filter(|n| !n.is_empty()).map(str::to_string),
+        },
+    )?;
+    tracing::info!(next_host, "handed off host");
+    Ok(format!("Handed off to {}. {}", next_host, pushed))
+}
+
+/// Take over a handoff naming this user: fetch the handed-off save, even over a local one that
+/// looks newer, and become the lease holder.
+pub fn accept_handoff(config: &SyncConfig, overrides: Overrides) -> Result<String, String> {
+    let lease = get_host_lease(config)?.ok_or("Nobody has handed off yet.")?;
+    if !offered_to_us(config, &lease) {
+        return Err(match &lease.next_host {
+            Some(next) => format!("The handoff is for {}, not for you.", next),
+            None => "No handoff is waiting.".to_string(),
+        });
+    }
+    let from = lease.handoff_by.clone().unwrap_or_else(|| "someone".to_string());
+    // Only the save pushed with the handoff (or a later one) may be taken over; an older one means
+    // the upload has not arrived yet.
+    let pushed_at = crate::push_meta::cloud_push_meta(config, "Save")?.map(|meta| meta.pushed_at);
+    if pushed_at.is_none_or(|at| at + HANDOFF_PUSH_SLACK_SECS < lease.handoff_at.unwrap_or(lease.since)) {
+        return Err(format!(
+            "The save {} handed off is not in the cloud yet. Try again in a moment.",
+            from
+        ));
+    }
+    // Fetch it even if the local save looks newer: that is what the handoff replaces.
+    let mut forced = config.clone();
+    forced.refetch_save = true;
+    let pulled = sync_pull(&forced, SyncTarget::Save, overrides)?;
+    put_lease(
+        config,
+        &HostLease {
+            holder: Some(crate::push_meta::uploader_name(config)),
+            holder_hostname: crate::push_meta::hostname(),
+            since: now(),
+            next_host: None,
+            ..lease
+        },
+    )?;
+    tracing::info!(from = %from, "took over as host");
+    Ok(format!("You are the host now (handed over by {}). {}", from, pulled))
+}
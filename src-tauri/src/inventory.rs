@@ -0,0 +1,199 @@
+//! Mod inventory: every file under the mods folder with its size, hash and, for DLLs, the file
+//! version from the PE version resource. Push uploads it as `Mods.inventory.json` (next to the
+//! mods of the active profile) so others can see what a pull would change before anything is
+//! extracted over their game. It is signed like the manifest (`Mods.inventory.sig`), so nobody
+//! with write access to the cloud can fake what that dialog shows.
+
+use crate::filter::PathFilter;
+use crate::manifest::build_manifest_filtered;
+use crate::sync::SyncConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Signature that starts VS_FIXEDFILEINFO inside a PE version resource.
+const FIXED_FILE_INFO_SIGNATURE: [u8; 4] = 0xFEEF04BDu32.to_le_bytes();
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ModEntry {
+    /// Path relative to the mods folder, with `/` separators.
+    pub path: String,
+    /// File name without extension, e.g. "BetterTrash".
+    pub name: String,
+    /// File version (e.g. "1.2.0.0") for DLLs that carry one.
+    pub version: Option<String>,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModInventory {
+    pub created_at: i64,
+    pub mods: Vec<ModEntry>,
+}
+
+/// A mod present on both sides with different content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModUpdate {
+    pub path: String,
+    pub name: String,
+    pub local_version: Option<String>,
+    pub cloud_version: Option<String>,
+}
+
+/// What a mods pull would do: `added` are only in the cloud, `removed` only on this PC.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModsInventoryDiff {
+    /// When the cloud inventory was pushed (None if there is none yet).
+    pub cloud_created_at: Option<i64>,
+    pub added: Vec<ModEntry>,
+    pub removed: Vec<ModEntry>,
+    pub updated: Vec<ModUpdate>,
+    pub unchanged: usize,
+}
+
+/// File version from a PE file's VS_FIXEDFILEINFO, if it has a version resource.
+fn pe_file_version(data: &[u8]) -> Option<String> {
+    if !data.starts_with(b"MZ") {
+        return None;
+    }
+    let at = data
+        .windows(FIXED_FILE_INFO_SIGNATURE.len())
+        .position(|w| w == FIXED_FILE_INFO_SIGNATURE)?;
+    // Signature, struct version, then dwFileVersionMS and dwFileVersionLS.
+    let word = |offset: usize| -> Option<u32> {
+        let bytes = data.get(at + offset..at + offset + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    };
+    let (ms, ls) = (word(8)?, word(12)?);
+    if ms == 0 && ls == 0 {
+        return None;
+    }
+    Some(format!("{}.{}.{}.{}", ms >> 16, ms & 0xFFFF, ls >> 16, ls & 0xFFFF))
+}
+
+/// Inventory of the synced files under `mods_dir`.
+pub(crate) fn build_inventory(mods_dir: &Path, filter: &PathFilter) -> Result<ModInventory, String> {
+    let manifest = build_manifest_filtered(mods_dir, filter)?;
+    let mods = manifest
+        .files
+        .into_iter()
+        .map(|entry| {
+            let file = Path::new(&entry.path);
+            let is_dll = file
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("dll"));
+            let version = if is_dll {
+                fs::read(mods_dir.join(&entry.path))
+                    .ok()
+                    .and_then(|data| pe_file_version(&data))
+            } else {
+                None
+            };
+            ModEntry {
+                name: file
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| entry.path.clone()),
+                path: entry.path,
+                version,
+                size: entry.size,
+                sha256: entry.sha256,
+            }
+        })
+        .collect();
+    Ok(ModInventory {
+        created_at: manifest.created_at,
+        mods,
+    })
+}
+
+/// Serialized inventory of `mods_dir` and a detached signature over it, ready to upload as
+/// `Mods.inventory.json` and `Mods.inventory.sig`.
+pub(crate) fn signed_inventory(mods_dir: &Path, filter: &PathFilter) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let bytes = serde_json::to_vec_pretty(&build_inventory(mods_dir, filter)?).map_err(|e| e.to_string())?;
+    let sig = crate::signing::sign(&bytes)?;
+    Ok((bytes, sig))
+}
+
+/// Cloud object name of the inventory for the active mod profile, e.g. `Mods.inventory.json`.
+pub(crate) fn inventory_object(config: &SyncConfig) -> String {
+    format!("{}.inventory.json", crate::profiles::cloud_name(config, "Mods"))
+}
+
+/// Its signature, e.g. `Mods.inventory.sig`.
+pub(crate) fn inventory_sig_object(config: &SyncConfig) -> String {
+    format!("{}.inventory.sig", crate::profiles::cloud_name(config, "Mods"))
+}
+
+/// Upload the inventory of `mods_dir` through the cloud folder or Supabase.
+pub(crate) fn upload_inventory(config: &SyncConfig, mods_dir: &Path, filter: &PathFilter) -> Result<(), String> {
+    let remote = crate::blobs::Remote::from_config(config)?;
+    let (bytes, sig) = signed_inventory(mods_dir, filter)?;
+    remote.put(&inventory_sig_object(config), &crate::crypto::seal_for_upload(config, sig)?)?;
+    remote.put(&inventory_object(config), &crate::crypto::seal_for_upload(config, bytes)?)
+}
+
+/// The inventory last pushed to the cloud, if any. Its signature is checked like the mods
+/// manifest's: unsigned or untrusted inventories are refused unless unsigned mods are allowed.
+fn cloud_inventory(config: &SyncConfig) -> Result<Option<ModInventory>, String> {
+    let object = inventory_object(config);
+    let sig_object = inventory_sig_object(config);
+    let (data, sig) = if crate::git_sync::use_git(config) {
+        (
+            crate::git_sync::read_committed_file(config, &object)?,
+            crate::git_sync::read_committed_file(config, &sig_object)?,
+        )
+    } else {
+        let remote = crate::blobs::Remote::from_config(config)?;
+        let fetch = |name: &str| -> Result<Option<Vec<u8>>, String> {
+            remote
+                .get(name)?
+                .map(|data| crate::crypto::open_downloaded(config, data, name))
+                .transpose()
+        };
+        (fetch(&object)?, fetch(&sig_object)?)
+    };
+    let Some(bytes) = data else { return Ok(None) };
+    let mods_name = crate::profiles::cloud_name(config, "Mods");
+    crate::signing::verify_signature(config, &mods_name, &bytes, sig.as_deref())
+        .map_err(|e| format!("The mod inventory can't be trusted: {}", e))?;
+    serde_json::from_slice(&bytes)
+        .map(Some)
+        .map_err(|e| format!("{} is unreadable: {}", object, e))
+}
+
+/// Compare the cloud inventory with the local mods folder.
+pub fn get_mods_inventory(config: &SyncConfig) -> Result<ModsInventoryDiff, String> {
+    let (mods_dir, filter) = crate::sync::local_target(config, "Mods")?;
+    let local = build_inventory(&mods_dir, &filter)?;
+    let cloud = cloud_inventory(config)?;
+    let cloud_created_at = cloud.as_ref().map(|c| c.created_at);
+    let cloud_mods = cloud.map(|c| c.mods).unwrap_or_default();
+
+    let mut local_by_path: HashMap<String, ModEntry> =
+        local.mods.into_iter().map(|m| (m.path.clone(), m)).collect();
+    let mut diff = ModsInventoryDiff {
+        cloud_created_at,
+        added: Vec::new(),
+        removed: Vec::new(),
+        updated: Vec::new(),
+        unchanged: 0,
+    };
+    for cloud_mod in cloud_mods {
+        match local_by_path.remove(&cloud_mod.path) {
+            Some(local_mod) if local_mod.sha256 == cloud_mod.sha256 => diff.unchanged += 1,
+            Some(local_mod) => diff.updated.push(ModUpdate {
+                path: cloud_mod.path,
+                name: cloud_mod.name,
+                local_version: local_mod.version,
+                cloud_version: cloud_mod.version,
+            }),
+            None => diff.added.push(cloud_mod),
+        }
+    }
+    diff.removed = local_by_path.into_values().collect();
+    diff.removed.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(diff)
+}
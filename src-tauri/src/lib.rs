@@ -1,8 +1,32 @@
+mod activity;
+mod archive;
+mod autostart;
+mod blobs;
+mod cloud_watch;
+mod crypto;
+mod discovery;
+mod filter;
+mod game_process;
 mod sync;
 mod supabase_sync;
+mod git_sync;
+mod tray;
+mod handoff;
+mod inventory;
+mod logging;
+mod manifest;
+mod notifications;
+mod presence;
+mod profiles;
+mod push_meta;
+mod save_meta;
+mod signing;
+mod vdf;
 
 use serde::{Deserialize, Serialize};
-use sync::{load_config, save_config, sync_pull, sync_push, SyncConfig, SyncTarget};
+use std::time::Instant;
+use tauri::Emitter;
+use sync::{load_config, save_config, sync_pull, sync_push, Overrides, SyncConfig, SyncTarget};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct SyncResult {
@@ -17,7 +41,9 @@ fn get_config() -> Result<SyncConfig, String> {
 
 #[tauri::command]
 fn set_config(config: SyncConfig) -> Result<(), String> {
-    save_config(&config)
+    save_config(&config)?;
+    tray::request_check();
+    Ok(())
 }
 
 #[tauri::command]
@@ -27,79 +53,313 @@ fn get_sync_status() -> Result<sync::SyncStatus, String> {
 }
 
 #[tauri::command]
-fn do_sync_pull(target: Option<SyncTarget>, force: Option<bool>) -> Result<SyncResult, String> {
+fn do_sync_pull(app: tauri::AppHandle, target: Option<SyncTarget>, overrides: Option<Overrides>) -> Result<SyncResult, String> {
+    let _running = sync::sync_lock()?;
     let config = load_config()?;
     let target = target.unwrap_or(SyncTarget::Both);
-    let force = force.unwrap_or(false);
-    let message = sync_pull(&config, target, force)?;
-    Ok(SyncResult { ok: true, message })
+    let overrides = overrides.unwrap_or_default();
+    let started = Instant::now();
+    let result = sync_pull(&config, target, overrides);
+    tracing::info!(?target, ?overrides, elapsed_ms = started.elapsed().as_millis() as u64, ok = result.is_ok(), "fetch done");
+    activity::record(&config, "fetch", activity::target_name(target), overrides.any(), &result);
+    notifications::sync_result(&app, "fetch", &result);
+    tray::request_check();
+    Ok(SyncResult { ok: true, message: result? })
 }
 
+/// Upload; `note` (e.g. "finished the warehouse") is recorded with the push for the group to see.
 #[tauri::command]
-fn do_sync_push(target: Option<SyncTarget>, force: Option<bool>) -> Result<SyncResult, String> {
-    let config = load_config()?;
+fn do_sync_push(
+    app: tauri::AppHandle,
+    target: Option<SyncTarget>,
+    overrides: Option<Overrides>,
+    note: Option<String>,
+) -> Result<SyncResult, String> {
+    let _running = sync::sync_lock()?;
+    let mut config = load_config()?;
+    config.push_note = note;
     let target = target.unwrap_or(SyncTarget::Both);
-    let force = force.unwrap_or(false);
-    let message = sync_push(&config, target, force)?;
+    let overrides = overrides.unwrap_or_default();
+    let started = Instant::now();
+    let result = sync_push(&config, target, overrides);
+    tracing::info!(?target, ?overrides, elapsed_ms = started.elapsed().as_millis() as u64, ok = result.is_ok(), "upload done");
+    activity::record(&config, "upload", activity::target_name(target), overrides.any(), &result);
+    notifications::sync_result(&app, "upload", &result);
+    tray::request_check();
+    Ok(SyncResult { ok: true, message: result? })
+}
+
+/// Store the group passphrase in the OS credential store (empty/None removes it).
+#[tauri::command]
+fn set_group_passphrase(passphrase: Option<String>) -> Result<(), String> {
+    crypto::set_group_passphrase(passphrase.as_deref())
+}
+
+#[tauri::command]
+fn has_group_passphrase() -> bool {
+    crypto::has_group_passphrase()
+}
+
+/// Public half of this PC's signing key, for the group's trusted signers list.
+#[tauri::command]
+fn get_identity_public_key() -> Result<String, String> {
+    signing::identity_public_key()
+}
+
+#[tauri::command]
+fn get_save_history(limit: Option<usize>) -> Result<Vec<git_sync::SaveVersion>, String> {
+    let config = load_config()?;
+    git_sync::save_history(&config, limit.unwrap_or(50))
+}
+
+#[tauri::command]
+fn restore_save_version(commit: String, overrides: Option<Overrides>) -> Result<SyncResult, String> {
+    let _running = sync::sync_lock()?;
+    let config = load_config()?;
+    let overrides = overrides.unwrap_or_default();
+    let result = git_sync::restore_save_version(&config, &commit, overrides);
+    activity::record(&config, "restore", "save", overrides.any(), &result);
+    Ok(SyncResult { ok: true, message: result? })
+}
+
+/// Compare the cloud mod inventory with the local mods folder (added / removed / updated).
+#[tauri::command]
+fn get_mods_inventory() -> Result<inventory::ModsInventoryDiff, String> {
+    let config = load_config()?;
+    inventory::get_mods_inventory(&config)
+}
+
+/// Pushed versions of "Save" or "Mods" kept by the blob store, newest first.
+#[tauri::command]
+fn get_blob_history(name: String, limit: Option<usize>) -> Result<Vec<blobs::BlobVersion>, String> {
+    let config = load_config()?;
+    blobs::list_versions(&config, &name, limit.unwrap_or(50))
+}
+
+#[tauri::command]
+fn restore_blob_version(name: String, id: String, overrides: Option<Overrides>) -> Result<SyncResult, String> {
+    let _running = sync::sync_lock()?;
+    let config = load_config()?;
+    let overrides = overrides.unwrap_or_default();
+    let result = blobs::restore_version(&config, &name, &id, overrides);
+    activity::record(&config, "restore", &name.to_lowercase(), overrides.any(), &result);
+    Ok(SyncResult { ok: true, message: result? })
+}
+
+/// Prune blob-store history to `keep_versions` per folder (if given) and delete unreferenced blobs.
+#[tauri::command]
+fn collect_blob_garbage(keep_versions: Option<usize>) -> Result<blobs::GcReport, String> {
+    let _running = sync::sync_lock()?;
+    let config = load_config()?;
+    blobs::collect_garbage(&config, keep_versions)
+}
+
+/// Save folders and game installs found in the usual places, for the settings to pick from.
+#[tauri::command]
+fn discover_paths() -> discovery::DiscoveredPaths {
+    discovery::discover_paths()
+}
+
+/// Mod profiles known on this PC or in the cloud, the default first.
+#[tauri::command]
+fn list_mod_profiles() -> Result<profiles::ModProfiles, String> {
+    let config = load_config()?;
+    profiles::list_mod_profiles(&config)
+}
+
+/// Make `profile` the active mod set (a new name starts an empty profile).
+#[tauri::command]
+fn switch_mod_profile(profile: String) -> Result<SyncResult, String> {
+    let _running = sync::sync_lock()?;
+    let config = load_config()?;
+    let result = profiles::switch_mod_profile(&config, &profile);
+    activity::record(&config, "switch_profile", "mods", false, &result);
+    Ok(SyncResult { ok: true, message: result? })
+}
+
+/// Past fetches, uploads, restores and profile switches of the whole group, newest first.
+/// `target` is "save" or "mods", `outcome` one of "ok", "forced", "warning", "failed".
+#[tauri::command]
+fn get_activity(
+    target: Option<String>,
+    user: Option<String>,
+    outcome: Option<String>,
+    limit: Option<usize>,
+) -> Result<activity::ActivityFeed, String> {
+    let config = load_config()?;
+    activity::get_activity(
+        &config,
+        target.as_deref(),
+        user.as_deref(),
+        outcome.as_deref(),
+        limit.unwrap_or(200),
+    )
+}
+
+/// Group members with SyncONE open right now, and who is hosting.
+#[tauri::command]
+fn get_presence() -> Result<presence::Presence, String> {
+    let config = load_config()?;
+    Ok(presence::get_presence(&config))
+}
+
+/// Upload the save and name `next_host` as the next host; their SyncONE offers to take over.
+#[tauri::command]
+fn handoff(next_host: String, overrides: Option<Overrides>, note: Option<String>) -> Result<SyncResult, String> {
+    let _running = sync::sync_lock()?;
+    let mut config = load_config()?;
+    config.push_note = note;
+    let overrides = overrides.unwrap_or_default();
+    let result = handoff::handoff(&config, &next_host, overrides);
+    activity::record(&config, "handoff", "save", overrides.any(), &result);
+    Ok(SyncResult { ok: true, message: result? })
+}
+
+/// Take over the handoff naming this user: fetch the save and become the host.
+#[tauri::command]
+fn accept_handoff(overrides: Option<Overrides>) -> Result<SyncResult, String> {
+    let _running = sync::sync_lock()?;
+    let config = load_config()?;
+    let overrides = overrides.unwrap_or_default();
+    let result = handoff::accept_handoff(&config, overrides);
+    activity::record(&config, "accept_handoff", "save", overrides.any(), &result);
+    Ok(SyncResult { ok: true, message: result? })
+}
+
+/// The host lease if it names this user as the next host, for UIs that missed the event.
+#[tauri::command]
+fn get_handoff_offer() -> Result<Option<handoff::HostLease>, String> {
+    let config = load_config()?;
+    let lease = handoff::get_host_lease(&config)?;
+    Ok(lease.filter(|lease| handoff::offered_to_us(&config, lease)))
+}
+
+/// Whether cloud changes arrive as `cloud-changed` events right now (else the UI has to poll).
+#[tauri::command]
+fn get_cloud_watch_active() -> bool {
+    cloud_watch::is_active()
+}
+
+/// Zip the log files, settings and activity log (keys and passwords masked) to `path`.
+#[tauri::command]
+fn export_diagnostics(path: String) -> Result<SyncResult, String> {
+    let config = load_config()?;
+    let message = logging::export_diagnostics(&config, std::path::Path::new(&path))?;
     Ok(SyncResult { ok: true, message })
 }
 
+/// Register or remove SyncONE at login and remember the choice.
 #[tauri::command]
-fn get_startup_folder() -> Result<String, String> {
-    #[cfg(windows)]
-    {
-        let appdata = std::env::var("APPDATA")
-            .map_err(|_| "APPDATA not set".to_string())?;
-        let path = std::path::Path::new(&appdata)
-            .join("Microsoft\\Windows\\Start Menu\\Programs\\Startup");
-        Ok(path.to_string_lossy().to_string())
-    }
-    #[cfg(not(windows))]
-    {
-        let home = std::env::var("HOME").map_err(|_| "HOME not set".to_string())?;
-        Ok(std::path::Path::new(&home)
-            .join(".config/autostart")
-            .to_string_lossy()
-            .to_string())
-    }
-}
-
-#[tauri::command]
-fn open_startup_folder() -> Result<(), String> {
-    let path = get_startup_folder()?;
-    #[cfg(windows)]
-    {
-        std::process::Command::new("explorer")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-    }
-    #[cfg(not(windows))]
-    {
-        #[cfg(target_os = "macos")]
-        std::process::Command::new("open").arg(&path).spawn().map_err(|e| e.to_string())?;
-        #[cfg(not(target_os = "macos"))]
-        std::process::Command::new("xdg-open").arg(&path).spawn().map_err(|e| e.to_string())?;
-    }
-    Ok(())
+fn set_run_at_startup(enabled: bool) -> Result<(), String> {
+    autostart::set_autostart(enabled)?;
+    let mut config = load_config()?;
+    config.run_at_startup = enabled;
+    save_config(&config)
+}
+
+/// Hand-made startup shortcuts for SyncONE that would start it a second time at login.
+#[tauri::command]
+fn get_legacy_startup_entries() -> Vec<String> {
+    autostart::legacy_entries()
+        .into_iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect()
+}
+
+/// How this process was started (`--minimized`, `--auto-pull`).
+#[tauri::command]
+fn get_launch_options() -> autostart::LaunchOptions {
+    autostart::launch_options()
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    logging::init();
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "SyncONE started");
+    presence::start_heartbeat();
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
+        .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            tray::init(app.handle())?;
+            if load_config().is_ok_and(|config| config.run_at_startup) {
+                // Keep the login entry pointing at this executable after an update or a move.
+                let _ = autostart::set_autostart(true);
+            }
+            let launch = autostart::launch_options();
+            if !launch.minimized {
+                tray::show_window(app.handle());
+            }
+            if launch.auto_pull {
+                tray::run_sync(app.handle(), "fetch");
+            }
+            let handle = app.handle().clone();
+            cloud_watch::start(move |event| {
+                let _ = match event {
+                    cloud_watch::WatchEvent::Changed(change) => {
+                        tray::request_check();
+                        notifications::notify(&handle, notifications::Kind::CloudChange, "New version in the cloud", &change.message);
+                        handle.emit("cloud-changed", change)
+                    }
+                    cloud_watch::WatchEvent::HandoffOffered(lease) => {
+                        notifications::handoff_offered(&handle, &lease);
+                        handle.emit("handoff-offered", lease)
+                    }
+                    cloud_watch::WatchEvent::LeaseChanged(lease) => {
+                        notifications::lease_changed(&handle, &lease);
+                        handle.emit("host-changed", lease)
+                    }
+                    cloud_watch::WatchEvent::Active(active) => handle.emit("cloud-watch", active),
+                };
+            });
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            // Closing the window leaves SyncONE running in the tray unless configured otherwise.
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if !load_config().is_ok_and(|config| config.quit_on_close) {
+                    api.prevent_close();
+                    let _ = window.hide();
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             get_config,
             set_config,
             get_sync_status,
             do_sync_pull,
             do_sync_push,
-            get_startup_folder,
-            open_startup_folder
+            set_group_passphrase,
+            has_group_passphrase,
+            get_identity_public_key,
+            get_save_history,
+            restore_save_version,
+            get_mods_inventory,
+            get_blob_history,
+            restore_blob_version,
+            collect_blob_garbage,
+            discover_paths,
+            list_mod_profiles,
+            switch_mod_profile,
+            get_activity,
+            get_presence,
+            get_cloud_watch_active,
+            handoff,
+            accept_handoff,
+            get_handoff_offer,
+            export_diagnostics,
+            set_run_at_startup,
+            get_legacy_startup_entries,
+            get_launch_options
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                presence::go_offline();
+            }
+        });
 }
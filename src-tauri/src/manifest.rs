@@ -0,0 +1,117 @@
+//! File manifests: relative path, size and SHA-256 of every file under a folder.
+//! Written last on push so a pull can tell when a cloud copy is complete.
+
+use crate::filter::PathFilter;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct ManifestEntry {
+    /// Path relative to the synced folder, always with `/` separators.
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Unix timestamp (seconds) of when the manifest was built, i.e. when it was pushed.
+    pub created_at: i64,
+    /// Cloud name it was pushed as (see `profiles::cloud_name`). Part of the signed bytes, so a
+    /// signed copy can't be replayed as another folder or profile. Missing in older manifests.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Mod profile of `name`, if any.
+    #[serde(default)]
+    pub profile: Option<String>,
+    pub files: Vec<ManifestEntry>,
+}
+
+/// Lowercase hex SHA-256 of a byte slice.
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Lowercase hex SHA-256 of a file, streamed so large mod DLLs are not loaded at once.
+pub(crate) fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut f = fs::File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut f, &mut hasher).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// Build a manifest of every file under `root`, sorted by path.
+pub(crate) fn build_manifest(root: &Path) -> Result<Manifest, String> {
+    build_manifest_filtered(root, &PathFilter::all())
+}
+
+/// Build a manifest of the files under `root` that pass `filter`, sorted by path.
+pub(crate) fn build_manifest_filtered(root: &Path, filter: &PathFilter) -> Result<Manifest, String> {
+    fn walk(dir: &Path, prefix: &str, filter: &PathFilter, out: &mut Vec<ManifestEntry>) -> Result<(), String> {
+        for e in fs::read_dir(dir).map_err(|e| e.to_string())? {
+            let e = e.map_err(|e| e.to_string())?;
+            let name = e.file_name();
+            let rel = if prefix.is_empty() {
+                name.to_string_lossy().to_string()
+            } else {
+                format!("{}/{}", prefix, name.to_string_lossy())
+            };
+            let full = dir.join(&name);
+            if e.file_type().map_err(|e| e.to_string())?.is_dir() {
+                if filter.enters(&rel) {
+                    walk(&full, &rel, filter, out)?;
+                }
+            } else if filter.matches(&rel) {
+                let size = e.metadata().map_err(|e| e.to_string())?.len();
+                out.push(ManifestEntry {
+                    path: rel,
+                    size,
+                    sha256: sha256_file(&full)?,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    if root.is_dir() {
+        walk(root, "", filter, &mut files)?;
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(Manifest {
+        created_at: chrono::Utc::now().timestamp(),
+        name: None,
+        profile: None,
+        files,
+    })
+}
+
+/// Files listed in the manifest that are missing under `root` or differ in size/hash.
+/// An empty result means the folder matches the manifest.
+pub(crate) fn manifest_mismatches(root: &Path, manifest: &Manifest) -> Vec<String> {
+    manifest
+        .files
+        .iter()
+        .filter(|entry| {
+            let path = root.join(&entry.path);
+            match fs::metadata(&path) {
+                Ok(meta) if meta.len() == entry.size => {
+                    sha256_file(&path).map_or(true, |h| h != entry.sha256)
+                }
+                _ => true,
+            }
+        })
+        .map(|entry| entry.path.clone())
+        .collect()
+}
+
+pub(crate) fn read_manifest(path: &Path) -> Option<Manifest> {
+    let bytes = fs::read(path).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
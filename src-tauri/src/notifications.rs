@@ -0,0 +1,81 @@
+//! Desktop notifications for what happens while the window is not in front: the result of a
+//! fetch or upload (the auto-fetch at startup included), warnings waiting for a decision, handoffs
+//! and new versions pushed by others. Each kind can be turned off in the settings; with the window
+//! in front nothing is shown, the window already says it.
+
+use crate::handoff::HostLease;
+use crate::sync::{load_config, NotificationSettings};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Kind {
+    SyncResult,
+    Warning,
+    Handoff,
+    CloudChange,
+}
+
+fn enabled(settings: &NotificationSettings, kind: Kind) -> bool {
+    match kind {
+        Kind::SyncResult => settings.sync_results,
+        Kind::Warning => settings.warnings,
+        Kind::Handoff => settings.handoffs,
+        Kind::CloudChange => settings.cloud_changes,
+    }
+}
+
+fn window_in_front(app: &AppHandle) -> bool {
+    app.get_webview_window("main")
+        .is_some_and(|w| w.is_visible().unwrap_or(false) && w.is_focused().unwrap_or(false))
+}
+
+pub(crate) fn notify(app: &AppHandle, kind: Kind, title: &str, body: &str) {
+    let Ok(config) = load_config() else { return };
+    if !enabled(&config.notifications, kind) || window_in_front(app) {
+        return;
+    }
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        tracing::warn!(error = %e, "could not show notification");
+    }
+}
+
+/// Notify the outcome of `action` ("fetch" or "upload").
+pub(crate) fn sync_result(app: &AppHandle, action: &str, result: &Result<String, String>) {
+    let what = if action == "fetch" { "Fetch" } else { "Upload" };
+    match result {
+        Ok(message) => notify(app, Kind::SyncResult, &format!("{} finished", what), message),
+        Err(e) => match crate::activity::forceable_warning(e) {
+            Some(warning) => notify(
+                app,
+                Kind::Warning,
+                &format!("{} needs your decision", what),
+                &format!("{}\n\nOpen SyncONE to go ahead or cancel.", warning.trim()),
+            ),
+            None => notify(app, Kind::SyncResult, &format!("{} failed", what), e),
+        },
+    }
+}
+
+pub(crate) fn handoff_offered(app: &AppHandle, lease: &HostLease) {
+    let by = lease.handoff_by.as_deref().unwrap_or("Someone");
+    notify(
+        app,
+        Kind::Handoff,
+        "You're the next host",
+        &format!("{} handed hosting over to you. Open SyncONE to fetch the save and take over.", by),
+    );
+}
+
+pub(crate) fn lease_changed(app: &AppHandle, lease: &HostLease) {
+    let body = match (&lease.holder, &lease.next_host) {
+        (Some(holder), _) => format!("{} is the host now.", holder),
+        (None, Some(next)) => format!(
+            "{} handed hosting over to {}.",
+            lease.handoff_by.as_deref().unwrap_or("Someone"),
+            next
+        ),
+        (None, None) => return,
+    };
+    notify(app, Kind::Handoff, "Host changed", &body);
+}
@@ -0,0 +1,205 @@
+//! Presence: while SyncONE is open, each PC publishes `presence/<user>@<host>.json` every minute
+//! with who is there, which mod profile they use and whether the game is running, so the group can
+//! see who is on without asking.
+//!
+//! A record whose heartbeat is older than `STALE_AFTER_SECS` belongs to a PC that closed SyncONE
+//! without saying goodbye (crash, shutdown, no network); it is left out and deleted by the next
+//! `get_presence`. Like the activity feed, presence needs a cloud folder or Supabase.
+
+use crate::sync::{latest_mtime, local_target, SyncConfig};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const PRESENCE_DIR: &str = "presence";
+/// How often a running SyncONE refreshes its record.
+const HEARTBEAT_SECS: u64 = 60;
+/// A record not refreshed for this long is offline. Generous, because desktop sync clients
+/// (Drive, OneDrive) can take a few minutes to pass a cloud folder change on.
+const STALE_AFTER_SECS: i64 = 5 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresenceRecord {
+    pub user: String,
+    pub hostname: Option<String>,
+    /// Active mod profile (None = default).
+    pub profile: Option<String>,
+    pub game_running: bool,
+    /// The game is running and has written the save since it started, so this PC hosts.
+    #[serde(default)]
+    pub hosting: bool,
+    /// Unix seconds when this SyncONE was opened.
+    pub online_since: i64,
+    /// Unix seconds of the last refresh.
+    pub heartbeat_at: i64,
+    #[serde(default)]
+    pub app_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresenceMember {
+    #[serde(flatten)]
+    pub record: PresenceRecord,
+    /// This is our own record.
+    pub this_pc: bool,
+}
+
+/// Who is online right now, for the UI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Presence {
+    /// Online members, sorted by name.
+    pub members: Vec<PresenceMember>,
+    /// Name of the member hosting: whose game hosts right now, else the holder of the host lease
+    /// (see `handoff`) if they are online.
+    pub host: Option<String>,
+    /// Why presence is unavailable (git backend, unreadable cloud); `members` is then empty.
+    pub error: Option<String>,
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// This PC's presence object.
+fn own_object(config: &SyncConfig) -> String {
+    let user = crate::push_meta::uploader_name(config);
+    format!(
+        "{}/{}.json",
+        PRESENCE_DIR,
+        crate::push_meta::pc_id(&user, crate::push_meta::hostname().as_deref())
+    )
+}
+
+/// What the heartbeat remembers between beats.
+struct Heartbeat {
+    online_since: i64,
+    /// When the game was first seen running (None while it is not).
+    game_since: Option<SystemTime>,
+    hosting: bool,
+}
+
+impl Heartbeat {
+    fn new() -> Self {
+        Heartbeat { online_since: now(), game_since: None, hosting: false }
+    }
+
+    fn beat(&mut self, config: &SyncConfig) -> Result<(), String> {
+        if crate::git_sync::use_git(config) {
+            return Ok(());
+        }
+        let game_running = crate::game_process::running_game(config).is_some();
+        if !game_running {
+            self.game_since = None;
+            self.hosting = false;
+        } else if self.game_since.is_none() {
+            self.game_since = Some(SystemTime::now());
+        }
+        // Joining someone else's game doesn't touch the local save; hosting writes it.
+        if let (Some(since), false) = (self.game_since, self.hosting) {
+            self.hosting = local_target(config, "Save")
+                .ok()
+                .and_then(|(dir, filter)| latest_mtime(&dir, &filter))
+                .is_some_and(|modified| modified >= since);
+        }
+        let record = PresenceRecord {
+            user: crate::push_meta::uploader_name(config),
+            hostname: crate::push_meta::hostname(),
+            profile: crate::profiles::active_profile(config).map(str::to_string),
+            game_running,
+            hosting: self.hosting,
+            online_since: self.online_since,
+            heartbeat_at: now(),
+            app_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        };
+        let data = serde_json::to_vec_pretty(&record).map_err(|e| e.to_string())?;
+        let remote = crate::blobs::Remote::from_config(config)?;
+        remote.put(&own_object(config), &crate::crypto::seal_for_upload(config, data)?)
+    }
+}
+
+/// Publish this PC's record now and then every `HEARTBEAT_SECS` until the app exits. Settings
+/// are re-read on every beat, so a changed name or backend is picked up.
+pub fn start_heartbeat() {
+    std::thread::spawn(|| {
+        let mut heartbeat = Heartbeat::new();
+        loop {
+            if let Ok(config) = crate::sync::load_config() {
+                if let Err(e) = heartbeat.beat(&config) {
+                    tracing::debug!(error = %e, "presence heartbeat failed");
+                }
+            }
+            std::thread::sleep(Duration::from_secs(HEARTBEAT_SECS));
+        }
+    });
+}
+
+/// Remove this PC's record when SyncONE closes.
+pub fn go_offline() {
+    let Ok(config) = crate::sync::load_config() else { return };
+    if crate::git_sync::use_git(&config) {
+        return;
+    }
+    if let Ok(remote) = crate::blobs::Remote::from_config(&config) {
+        let _ = remote.delete(&[own_object(&config)]);
+    }
+}
+
+fn read_presence(config: &SyncConfig) -> Result<Vec<PresenceMember>, String> {
+    let remote = crate::blobs::Remote::from_config(config)?;
+    let own = own_object(config);
+    let now = now();
+    let mut members = Vec::new();
+    let mut stale = Vec::new();
+    for entry in remote.list(PRESENCE_DIR)? {
+        let object = format!("{}/{}", PRESENCE_DIR, entry.name);
+        let Some(data) = remote.get(&object)? else { continue };
+        // One record we can't open (other passphrase, corrupt upload) must not hide everyone else.
+        let data = match crate::crypto::open_downloaded(config, data, &object) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!(object, error = %e, "skipping unreadable presence record");
+                continue;
+            }
+        };
+        let Ok(record) = serde_json::from_slice::<PresenceRecord>(&data) else { continue };
+        // Either clock may be off; trust whichever says the record is newer.
+        let last_seen = entry.modified.map_or(record.heartbeat_at, |m| m.max(record.heartbeat_at));
+        if now - last_seen > STALE_AFTER_SECS {
+            stale.push(object);
+        } else {
+            members.push(PresenceMember { this_pc: object == own, record });
+        }
+    }
+    if !stale.is_empty() {
+        tracing::debug!(count = stale.len(), "removing stale presence records");
+        let _ = remote.delete(&stale);
+    }
+    members.sort_by_key(|m| m.record.user.to_lowercase());
+    Ok(members)
+}
+
+/// Members whose SyncONE is open right now and who among them hosts.
+pub fn get_presence(config: &SyncConfig) -> Presence {
+    if crate::git_sync::use_git(config) {
+        return Presence {
+            members: Vec::new(),
+            host: None,
+            error: Some("Presence needs a cloud folder or Supabase; the git backend has nowhere to publish it.".to_string()),
+        };
+    }
+    match read_presence(config) {
+        Ok(members) => {
+            let host = members.iter().find(|m| m.record.hosting).map(|m| m.record.user.clone()).or_else(|| {
+                let holder = crate::handoff::get_host_lease(config).ok().flatten()?.holder?;
+                members
+                    .iter()
+                    .find(|m| m.record.user.eq_ignore_ascii_case(&holder))
+                    .map(|m| m.record.user.clone())
+            });
+            Presence { members, host, error: None }
+        }
+        Err(e) => Presence { members: Vec::new(), host: None, error: Some(e) },
+    }
+}
@@ -0,0 +1,227 @@
+//! Mod profiles: named mod sets, e.g. "vanilla" and "modded", for groups that switch between
+//! them. A profile's mods are stored under `mod_profiles/<profile>/` in the cloud; the default set
+//! stays at the top level as before profiles existed. (Not `mods/`: on Windows that is the same
+//! folder as the default set's `Mods/`, which a push mirrors and would wipe.)
+//!
+//! Switching keeps the outgoing set in a local cache (`mod_profiles/<profile>/` in the config
+//! dir), so switching back needs no download unless the profile's cloud inventory shows that
+//! someone uploaded different mods since.
+
+use crate::sync::{clear_matching, config_dir, local_target, mirror_dir, replace_dir_with, SyncConfig, SyncTarget};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+pub(crate) const DEFAULT_PROFILE: &str = "default";
+/// Cloud folder holding one subfolder per named profile.
+const PROFILE_DIR: &str = "mod_profiles";
+/// Local cache of inactive profiles, inside the config dir.
+const CACHE_DIR: &str = "mod_profiles";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModProfile {
+    pub name: String,
+    pub active: bool,
+    /// A copy is cached on this PC, so switching to it works offline.
+    pub cached: bool,
+    /// Uploaded to the cloud (always true for the default set, which lives at the top level).
+    pub in_cloud: bool,
+}
+
+/// Profiles for the picker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModProfiles {
+    pub profiles: Vec<ModProfile>,
+    /// Set when the cloud could not be listed; `profiles` then only has the default, cached and
+    /// active ones, and `in_cloud` is only known for the default.
+    pub cloud_error: Option<String>,
+}
+
+/// The active named profile, or None for the default set.
+pub(crate) fn active_profile(config: &SyncConfig) -> Option<&str> {
+    config
+        .mod_profile
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty() && !p.eq_ignore_ascii_case(DEFAULT_PROFILE))
+}
+
+/// Where `name` (Save or Mods) is stored in the cloud: `mod_profiles/<profile>/Mods` for the mods of a
+/// named profile, else `name` itself.
+pub(crate) fn cloud_name(config: &SyncConfig, name: &str) -> String {
+    match active_profile(config) {
+        Some(profile) if name == "Mods" => format!("{}/{}/Mods", PROFILE_DIR, profile),
+        _ => name.to_string(),
+    }
+}
+
+/// Cloud names of the mods of every named profile in `remote`, for blob garbage collection.
+pub(crate) fn profile_cloud_names(remote: &crate::blobs::Remote) -> Result<Vec<String>, String> {
+    Ok(remote
+        .list_dirs(PROFILE_DIR)?
+        .into_iter()
+        .map(|profile| format!("{}/{}/Mods", PROFILE_DIR, profile))
+        .collect())
+}
+
+/// Profile a cloud name (see `cloud_name`) belongs to: `None` for the save and the default mods.
+pub(crate) fn cloud_profile(name: &str) -> Option<&str> {
+    name.strip_prefix(PROFILE_DIR)?.strip_prefix('/')?.strip_suffix("/Mods")
+}
+
+/// Whether a cloud name (see `cloud_name`) refers to mods, of any profile.
+pub(crate) fn is_mods(name: &str) -> bool {
+    name == "Mods" || (name.starts_with(&format!("{}/", PROFILE_DIR)) && name.ends_with("/Mods"))
+}
+
+/// Profile names end up in cloud object names and local folder names.
+fn validate_profile_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 40
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid profile name \"{}\": use up to 40 letters, digits, '-' or '_'.",
+            name
+        ))
+    }
+}
+
+fn cache_root() -> Result<PathBuf, String> {
+    Ok(config_dir().map_err(|e| e.to_string())?.join(CACHE_DIR))
+}
+
+/// Copy the synced files of the mods target into the cache for the active profile.
+fn store_cache(config: &SyncConfig, profile: &str) -> Result<(), String> {
+    let (local, filter) = local_target(config, "Mods")?;
+    let cache = cache_root()?.join(profile);
+    fs::create_dir_all(&cache).map_err(|e| e.to_string())?;
+    mirror_dir(&local, &cache, &filter).map_err(|e| e.to_string())
+}
+
+/// Named profiles that have been uploaded to the cloud.
+fn cloud_profiles(config: &SyncConfig) -> Result<Vec<String>, String> {
+    if crate::git_sync::use_git(config) {
+        crate::git_sync::committed_dirs(config, PROFILE_DIR)
+    } else {
+        crate::blobs::Remote::from_config(config)?.list_dirs(PROFILE_DIR)
+    }
+}
+
+/// Profiles cached on this PC.
+fn cached_profiles() -> Result<Vec<String>, String> {
+    Ok(fs::read_dir(cache_root()?)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().is_dir())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// The spelling of `profile` already in use in the cloud or the cache. Names are compared
+/// ignoring case: "Coop" and "coop" are two folders in a bucket or git but one on Windows.
+fn known_spelling(config: &SyncConfig, profile: &str) -> Result<String, String> {
+    let in_cloud = cloud_profiles(config).unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "could not list cloud mod profiles");
+        Vec::new()
+    });
+    Ok(in_cloud
+        .into_iter()
+        .chain(cached_profiles()?)
+        .find(|known| known.eq_ignore_ascii_case(profile))
+        .unwrap_or_else(|| profile.to_string()))
+}
+
+/// Every profile known here or in the cloud, the default first. Offline, the cached and active
+/// profiles are still listed, so switching between them keeps working.
+pub fn list_mod_profiles(config: &SyncConfig) -> Result<ModProfiles, String> {
+    let active = active_profile(config).unwrap_or(DEFAULT_PROFILE).to_string();
+    let (in_cloud, cloud_error) = match cloud_profiles(config) {
+        Ok(names) => (names, None),
+        Err(e) => {
+            tracing::warn!(error = %e, "could not list cloud mod profiles");
+            (Vec::new(), Some(e))
+        }
+    };
+    let cached = cached_profiles()?;
+
+    let same = |a: &String, b: &String| a.eq_ignore_ascii_case(b);
+    let mut names = vec![DEFAULT_PROFILE.to_string()];
+    for name in in_cloud.iter().chain(&cached).chain(std::iter::once(&active)) {
+        if !names.iter().any(|known| same(known, name)) {
+            names.push(name.clone());
+        }
+    }
+    names[1..].sort_by_key(|name| name.to_lowercase());
+    let profiles = names
+        .into_iter()
+        .map(|name| ModProfile {
+            active: same(&name, &active),
+            cached: cached.iter().any(|c| same(c, &name)),
+            in_cloud: name == DEFAULT_PROFILE || in_cloud.iter().any(|c| same(c, &name)),
+            name,
+        })
+        .collect();
+    Ok(ModProfiles { profiles, cloud_error })
+}
+
+/// Make `profile` the active mod set: cache the current one, then fill the mods target from the
+/// cache, and from the cloud when its copy of the profile differs. A profile that exists nowhere
+/// yet starts empty. The choice is saved in config, so Fetch and Upload use it.
+pub fn switch_mod_profile(config: &SyncConfig, profile: &str) -> Result<String, String> {
+    let profile = profile.trim();
+    let profile = if profile.is_empty() || profile.eq_ignore_ascii_case(DEFAULT_PROFILE) {
+        DEFAULT_PROFILE.to_string()
+    } else {
+        validate_profile_name(profile)?;
+        known_spelling(config, profile)?
+    };
+    let profile = profile.as_str();
+    let current = active_profile(config).unwrap_or(DEFAULT_PROFILE);
+    if current.eq_ignore_ascii_case(profile) {
+        return Ok(format!("{} is already the active mod profile.", current));
+    }
+    if let Some(exe) = crate::game_process::running_game(config) {
+        return Err(format!("Close Schedule I ({}) before switching mod profiles.", exe));
+    }
+
+    store_cache(config, current)?;
+    let mut switched = config.clone();
+    switched.mod_profile = (profile != DEFAULT_PROFILE).then(|| profile.to_string());
+    let (local, filter) = local_target(&switched, "Mods")?;
+    let cache = cache_root()?.join(profile);
+    if cache.is_dir() {
+        replace_dir_with(&cache, &local, &filter).map_err(|e| e.to_string())?;
+    } else {
+        clear_matching(&local, &filter).map_err(|e| e.to_string())?;
+    }
+    crate::sync::save_config(&switched)?;
+
+    let in_cloud = crate::sync::get_sync_status(&switched).mods_cloud_mtime.is_some();
+    // Compare contents, not mtimes: restoring the cache made every file look new.
+    let cache_is_current = crate::inventory::get_mods_inventory(&switched).is_ok_and(|diff| {
+        diff.cloud_created_at.is_some() && diff.added.is_empty() && diff.removed.is_empty() && diff.updated.is_empty()
+    });
+
+    if cache_is_current {
+        Ok(format!("Switched to mod profile {}.", profile))
+    } else if in_cloud {
+        // The folder's mtime is fresh after switching, so skip the "is the cloud newer" check.
+        switched.refetch_mods = true;
+        crate::sync::sync_pull(&switched, SyncTarget::Mods, crate::sync::Overrides::default())
+            .map(|_| format!("Switched to mod profile {} and fetched its mods.", profile))
+            .map_err(|e| format!("Switched to mod profile {}, but fetching its mods failed: {}", profile, e))
+    } else if cache.is_dir() {
+        Ok(format!("Switched to mod profile {} (not uploaded yet).", profile))
+    } else {
+        Ok(format!(
+            "Switched to new mod profile {}. Install its mods and upload to share it with the group.",
+            profile
+        ))
+    }
+}
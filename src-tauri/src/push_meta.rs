@@ -0,0 +1,146 @@
+//! Who pushed, from where and why: a small record uploaded as `<name>.meta.json` next to every
+//! pushed Save and Mods (the cloud itself only knows when an object changed). Shown in the sync
+//! status as "last pushed by Anna 2h ago".
+//!
+//! The save's record also holds what it was last played with – the game build and the mod
+//! profile – for the check in `save_meta`.
+
+use crate::filter::PathFilter;
+use crate::sync::SyncConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushMeta {
+    pub pushed_at: i64,
+    /// Display name from config, else the OS user name.
+    #[serde(default)]
+    pub pushed_by: Option<String>,
+    #[serde(default)]
+    pub hostname: Option<String>,
+    /// SyncONE version that pushed.
+    #[serde(default)]
+    pub app_version: Option<String>,
+    /// Free text entered with the upload, e.g. "finished the warehouse".
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Highest LifetimeEarnings in the pushed save (saves only).
+    #[serde(default)]
+    pub lifetime_earnings: Option<f64>,
+    #[serde(default)]
+    pub file_count: Option<usize>,
+    /// Steam build ID of the pusher's game install, if it could be found.
+    #[serde(default)]
+    pub game_build: Option<String>,
+    /// Mod profile that was active, for the mismatch warning.
+    #[serde(default)]
+    pub mod_profile: Option<String>,
+}
+
+/// Cloud object holding the record for `name` (a cloud name, see `profiles::cloud_name`).
+pub(crate) fn meta_object(name: &str) -> String {
+    format!("{}.meta.json", name)
+}
+
+/// Name recorded as the pusher (and used as the git commit author).
+pub(crate) fn uploader_name(config: &SyncConfig) -> String {
+    config
+        .display_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string)
+        .or_else(|| std::env::var("USERNAME").ok())
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+pub(crate) fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+}
+
+/// `<user>@<host>` reduced to characters safe in an object name, naming this PC's own objects.
+pub(crate) fn pc_id(user: &str, hostname: Option<&str>) -> String {
+    format!("{}@{}", user, hostname.unwrap_or("unknown"))
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '@' | '-' | '_' | '.') { c } else { '_' })
+        .collect()
+}
+
+/// Number of files under `dir` that `filter` syncs.
+fn count_files(dir: &Path, filter: &PathFilter) -> usize {
+    fn walk(dir: &Path, prefix: &str, filter: &PathFilter) -> usize {
+        let Ok(entries) = fs::read_dir(dir) else { return 0 };
+        entries
+            .flatten()
+            .map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                let rel = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+                if e.path().is_dir() {
+                    if filter.enters(&rel) { walk(&e.path(), &rel, filter) } else { 0 }
+                } else {
+                    usize::from(filter.matches(&rel))
+                }
+            })
+            .sum()
+    }
+    walk(dir, "", filter)
+}
+
+/// The record for pushing `local` (filtered by `filter`) as `name`.
+pub(crate) fn build_push_meta(config: &SyncConfig, name: &str, local: &Path, filter: &PathFilter) -> PushMeta {
+    PushMeta {
+        pushed_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0),
+        pushed_by: Some(uploader_name(config)),
+        hostname: hostname(),
+        app_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        note: config.push_note.as_deref().map(str::trim).filter(|n| !n.is_empty()).map(str::to_string),
+        lifetime_earnings: (name == "Save")
+            .then(|| crate::supabase_sync::max_lifetime_earnings_from_save_dir(local))
+            .flatten(),
+        file_count: Some(count_files(local, filter)),
+        game_build: crate::discovery::installed_build(config),
+        mod_profile: crate::profiles::active_profile(config).map(str::to_string),
+    }
+}
+
+pub(crate) fn push_meta_bytes(config: &SyncConfig, name: &str, local: &Path, filter: &PathFilter) -> Result<Vec<u8>, String> {
+    serde_json::to_vec_pretty(&build_push_meta(config, name, local, filter)).map_err(|e| e.to_string())
+}
+
+/// Upload the record for `name` through the cloud folder or Supabase.
+pub(crate) fn upload_push_meta(config: &SyncConfig, name: &str, local: &Path, filter: &PathFilter) -> Result<(), String> {
+    let remote = crate::blobs::Remote::from_config(config)?;
+    let data = crate::crypto::seal_for_upload(config, push_meta_bytes(config, name, local, filter)?)?;
+    remote.put(&meta_object(name), &data)
+}
+
+/// The record of the last push of `name`, if it was pushed by a version that writes one.
+pub(crate) fn cloud_push_meta(config: &SyncConfig, name: &str) -> Result<Option<PushMeta>, String> {
+    let object = meta_object(name);
+    let data = if crate::git_sync::use_git(config) {
+        crate::git_sync::read_committed_file(config, &object)?
+    } else {
+        let remote = crate::blobs::Remote::from_config(config)?;
+        remote
+            .get(&object)?
+            .map(|data| crate::crypto::open_downloaded(config, data, &object))
+            .transpose()?
+    };
+    data.map(|bytes| parse_push_meta(&object, &bytes)).transpose()
+}
+
+/// A downloaded (and opened) `<name>.meta.json`.
+pub(crate) fn parse_push_meta(object: &str, bytes: &[u8]) -> Result<PushMeta, String> {
+    serde_json::from_slice(bytes).map_err(|e| format!("{} is unreadable: {}", object, e))
+}
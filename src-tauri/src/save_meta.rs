@@ -0,0 +1,68 @@
+//! What a save was last played with: the game build and the mod set. Recorded in the save's
+//! push record (`Save.meta.json`, see `push_meta`) and compared on fetch, together with the cloud
+//! mod inventory, because a save from a newer build or another mod set can break for the next
+//! host.
+
+use crate::sync::SyncConfig;
+
+/// Prefix of the error returned when the save's setup differs from this PC's; the UI offers to
+/// fetch anyway (`Overrides::ignore_setup_mismatch`).
+pub(crate) const SAVE_MISMATCH_PREFIX: &str = "SAVE_MISMATCH:";
+
+/// Refuse (unless overridden) to fetch a save last played on another game build or mod set. When
+/// mods are fetched along with the save (`with_mods`), only the game build is compared.
+pub(crate) fn check_save_meta(config: &SyncConfig, with_mods: bool) -> Result<(), String> {
+    let Some(cloud) = crate::push_meta::cloud_push_meta(config, "Save")? else { return Ok(()) };
+    let mut differences = Vec::new();
+    if let (Some(theirs), Some(ours)) = (&cloud.game_build, &crate::discovery::installed_build(config)) {
+        if theirs != ours {
+            differences.push(format!(
+                "Game build: the save was played on build {}, you have build {}. Update the game in Steam (or wait for the others to).",
+                theirs, ours
+            ));
+        }
+    }
+    if !with_mods {
+        differences.extend(mods_difference(config, cloud.mod_profile.as_deref())?);
+    }
+    if differences.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "{}The cloud save was last played with a different setup than yours.\n\n{}\n\nLoading it as is may break it.",
+        SAVE_MISMATCH_PREFIX,
+        differences.join("\n\n")
+    ))
+}
+
+/// How this PC's mods differ from the ones the save was played with: another profile, else files
+/// that a mods fetch would change. Paths kept on this PC (`mod_local_only`, excludes) don't count,
+/// so each PC can keep its own keybinds.
+fn mods_difference(config: &SyncConfig, save_profile: Option<&str>) -> Result<Option<String>, String> {
+    use crate::profiles::{active_profile, DEFAULT_PROFILE};
+    let theirs = save_profile.unwrap_or(DEFAULT_PROFILE);
+    let ours = active_profile(config).unwrap_or(DEFAULT_PROFILE);
+    if !theirs.eq_ignore_ascii_case(ours) {
+        return Ok(Some(format!(
+            "Mods: the save was played with mod profile {}, you use {}. Switch to that profile, or fetch the mods too.",
+            theirs, ours
+        )));
+    }
+    let diff = match crate::inventory::get_mods_inventory(config) {
+        Ok(diff) if diff.cloud_created_at.is_some() => diff,
+        Ok(_) => return Ok(None),
+        Err(e) => {
+            tracing::warn!(error = %e, "skipping the mods check: no usable cloud mod inventory");
+            return Ok(None);
+        }
+    };
+    let (_, filter) = crate::sync::local_target(config, "Mods")?;
+    let added = diff.added.iter().filter(|m| filter.matches(&m.path)).count();
+    let changed = added + diff.removed.len() + diff.updated.len();
+    Ok((changed > 0).then(|| {
+        format!(
+            "Mods: {} of your mod files differ from the cloud mods the save was played with (profile {}). Fetch the mods too.",
+            changed, theirs
+        )
+    }))
+}
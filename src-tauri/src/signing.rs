@@ -0,0 +1,347 @@
+//! Signed uploads: every push ships `<name>.manifest.json` plus a detached Ed25519 signature
+//! over it (`<name>.manifest.sig`). Pulls check the signature against the trusted signers in
+//! config and that the downloaded files match the manifest. The mod inventory is signed the same
+//! way (`<name>.inventory.sig`).
+//!
+//! Each member's identity key lives in the OS credential store next to the group passphrase.
+
+use crate::filter::PathFilter;
+use crate::manifest::{build_manifest_filtered, Manifest, ManifestEntry};
+use crate::sync::SyncConfig;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+const KEYRING_SERVICE: &str = "Syncone";
+const KEYRING_USER: &str = "identity-key";
+
+/// A group member whose pushes we accept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedSigner {
+    pub name: String,
+    /// Hex-encoded Ed25519 public key (as shown under "Your signing key" in the app).
+    pub public_key: String,
+}
+
+/// Contents of `<name>.manifest.sig` and `<name>.inventory.sig`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DetachedSignature {
+    signer: String,
+    signature: String,
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim();
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// This PC's signing key, generated and stored in the OS credential store on first use.
+fn identity_key() -> Result<SigningKey, String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| e.to_string())?;
+    match entry.get_password() {
+        Ok(hex) => {
+            let seed: [u8; 32] = from_hex(&hex)
+                .and_then(|b| b.try_into().ok())
+                .ok_or("Stored identity key is corrupt")?;
+            Ok(SigningKey::from_bytes(&seed))
+        }
+        Err(keyring::Error::NoEntry) => {
+            let mut seed = [0u8; 32];
+            OsRng.fill_bytes(&mut seed);
+            entry
+                .set_password(&crate::manifest::to_hex(&seed))
+                .map_err(|e| format!("Could not store identity key: {}", e))?;
+            Ok(SigningKey::from_bytes(&seed))
+        }
+        Err(e) => Err(format!("Could not read identity key: {}", e)),
+    }
+}
+
+/// Hex-encoded public half of this PC's identity key, to share with the group.
+pub fn identity_public_key() -> Result<String, String> {
+    Ok(crate::manifest::to_hex(identity_key()?.verifying_key().as_bytes()))
+}
+
+/// Manifest of the synced files in `dir`, bound to the cloud name `name`, and a detached
+/// signature over its bytes, ready to upload as `<name>.manifest.json` and `<name>.manifest.sig`.
+pub(crate) fn signed_manifest(name: &str, dir: &Path, filter: &PathFilter) -> Result<(Vec<u8>, Vec<u8>), String> {
+    signed_manifest_with(&identity_key()?, name, dir, filter)
+}
+
+fn signed_manifest_with(key: &SigningKey, name: &str, dir: &Path, filter: &PathFilter) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let mut manifest = build_manifest_filtered(dir, filter)?;
+    manifest.name = Some(name.to_string());
+    manifest.profile = crate::profiles::cloud_profile(name).map(str::to_string);
+    let manifest_bytes = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    let sig_bytes = sign_with(key, &manifest_bytes)?;
+    Ok((manifest_bytes, sig_bytes))
+}
+
+/// Detached signature over `data` with this PC's identity key, the contents of a `.sig` object.
+pub(crate) fn sign(data: &[u8]) -> Result<Vec<u8>, String> {
+    sign_with(&identity_key()?, data)
+}
+
+fn sign_with(key: &SigningKey, data: &[u8]) -> Result<Vec<u8>, String> {
+    let sig = DetachedSignature {
+        signer: crate::manifest::to_hex(key.verifying_key().as_bytes()),
+        signature: crate::manifest::to_hex(&key.sign(data).to_bytes()),
+    };
+    serde_json::to_vec_pretty(&sig).map_err(|e| e.to_string())
+}
+
+/// Check the signature on a downloaded manifest (or inventory). Returns the trusted signer's name.
+/// A bad signature is always an error; missing or unknown signatures are only refused for Mods
+/// (unless `allow_unsigned_mods`), because mods carry code.
+pub(crate) fn verify_signature(
+    config: &SyncConfig,
+    name: &str,
+    manifest_bytes: &[u8],
+    sig_bytes: Option<&[u8]>,
+) -> Result<Option<String>, String> {
+    verify_signature_as(config, name, manifest_bytes, sig_bytes, own_public_key)
+}
+
+fn own_public_key() -> Option<String> {
+    identity_public_key().ok()
+}
+
+/// `verify_signature` with the lookup of this PC's public key (`own_key`) passed in. It is only
+/// called for a valid signature, so pulling unsigned copies never creates an identity key.
+fn verify_signature_as(
+    config: &SyncConfig,
+    name: &str,
+    manifest_bytes: &[u8],
+    sig_bytes: Option<&[u8]>,
+    own_key: fn() -> Option<String>,
+) -> Result<Option<String>, String> {
+    let enforce = crate::profiles::is_mods(name) && !config.allow_unsigned_mods;
+    let sig: DetachedSignature = match sig_bytes.and_then(|b| serde_json::from_slice(b).ok()) {
+        Some(s) => s,
+        None if enforce => {
+            return Err(format!("{} in the cloud are not signed. Refusing to install unsigned mods (allow them in settings if you trust the source).", name))
+        }
+        None => return Ok(None),
+    };
+    let public: [u8; 32] = from_hex(&sig.signer)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| format!("{} signature has an invalid signer key", name))?;
+    let signature: [u8; 64] = from_hex(&sig.signature)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| format!("{} signature is malformed", name))?;
+    VerifyingKey::from_bytes(&public)
+        .and_then(|k| k.verify(manifest_bytes, &Signature::from_bytes(&signature)))
+        .map_err(|_| format!("{} signature is invalid – the upload may have been tampered with.", name))?;
+
+    if own_key().as_deref() == Some(sig.signer.as_str()) {
+        return Ok(Some("you".to_string()));
+    }
+    let trusted = config
+        .trusted_signers
+        .iter()
+        .find(|t| t.public_key.trim().eq_ignore_ascii_case(&sig.signer));
+    match trusted {
+        Some(t) => Ok(Some(t.name.clone())),
+        None if enforce => Err(format!(
+            "{} were signed by an unknown key ({}…). Add it to your trusted signers if it belongs to your group.",
+            name,
+            &sig.signer[..16.min(sig.signer.len())]
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Verify a downloaded copy of `name` (Save or Mods): the manifest signature per
+/// `verify_signature`, that the manifest was pushed as `name`, then that `actual` (the files we
+/// are about to install) matches it exactly. Returns the signer's name if the copy was signed by
+/// a trusted member.
+pub(crate) fn verify_download(
+    config: &SyncConfig,
+    name: &str,
+    manifest_bytes: Option<&[u8]>,
+    sig_bytes: Option<&[u8]>,
+    actual: &[ManifestEntry],
+) -> Result<Option<String>, String> {
+    verify_download_as(config, name, manifest_bytes, sig_bytes, actual, own_public_key)
+}
+
+/// `verify_download` with the lookup of this PC's public key passed in, see `verify_signature_as`.
+fn verify_download_as(
+    config: &SyncConfig,
+    name: &str,
+    manifest_bytes: Option<&[u8]>,
+    sig_bytes: Option<&[u8]>,
+    actual: &[ManifestEntry],
+    own_key: fn() -> Option<String>,
+) -> Result<Option<String>, String> {
+    let manifest_bytes = match manifest_bytes {
+        Some(b) => b,
+        None if crate::profiles::is_mods(name) && !config.allow_unsigned_mods => {
+            return Err(format!("{} in the cloud have no signed manifest. Refusing to install unsigned mods (allow them in settings if you trust the source).", name))
+        }
+        None => return Ok(None),
+    };
+    let signer = verify_signature_as(config, name, manifest_bytes, sig_bytes, own_key)?;
+    let manifest: Manifest = serde_json::from_slice(manifest_bytes)
+        .map_err(|e| format!("{} manifest is unreadable: {}", name, e))?;
+    check_binding(config, name, &manifest)?;
+    if manifest.files != actual {
+        let expected: std::collections::HashSet<&ManifestEntry> = manifest.files.iter().collect();
+        let differing = actual
+            .iter()
+            .filter(|e| !expected.contains(e))
+            .map(|e| e.path.as_str())
+            .chain(
+                manifest
+                    .files
+                    .iter()
+                    .filter(|e| !actual.contains(e))
+                    .map(|e| e.path.as_str()),
+            )
+            .take(5)
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(format!(
+            "The cloud copy of {} does not match its signed manifest ({}). The upload may be incomplete or tampered with.",
+            name, differing
+        ));
+    }
+    Ok(signer)
+}
+
+/// The signed manifest must name what is being pulled, or a signed copy of one folder or profile
+/// could be replayed as another. Manifests from before the binding are only accepted where
+/// unsigned copies are, i.e. not for Mods while signatures are enforced.
+fn check_binding(config: &SyncConfig, name: &str, manifest: &Manifest) -> Result<(), String> {
+    let profile = crate::profiles::cloud_profile(name);
+    match manifest.name.as_deref() {
+        Some(bound) if bound == name && manifest.profile.as_deref() == profile => Ok(()),
+        Some(bound) => Err(format!(
+            "The signed manifest for {} was pushed as {} (profile {}). Refusing a copy moved from another folder or profile.",
+            name,
+            bound,
+            manifest.profile.as_deref().unwrap_or("default")
+        )),
+        None if crate::profiles::is_mods(name) && !config.allow_unsigned_mods => Err(format!(
+            "{} in the cloud have an older signed manifest that does not name its profile. Upload them again with this version of SyncONE, or allow unsigned mods in settings.",
+            name
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{signed_manifest_with, verify_download_as, TrustedSigner};
+    use crate::filter::PathFilter;
+    use crate::manifest::{build_manifest, to_hex};
+    use crate::sync::SyncConfig;
+    use ed25519_dalek::SigningKey;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn folder(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("syncone_signing_{}_{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("plugins")).unwrap();
+        fs::write(dir.join("plugins/Mod.dll"), "code").unwrap();
+        dir
+    }
+
+    fn no_key() -> Option<String> {
+        None
+    }
+
+    fn trusting(key: &SigningKey) -> SyncConfig {
+        SyncConfig {
+            trusted_signers: vec![TrustedSigner {
+                name: "Anna".to_string(),
+                public_key: to_hex(key.verifying_key().as_bytes()),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn trusted_signature_verifies() {
+        let dir = folder("trusted");
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let (manifest, sig) = signed_manifest_with(&key, "Mods", &dir, &PathFilter::all()).unwrap();
+        let actual = build_manifest(&dir).unwrap().files;
+        let signer = verify_download_as(&trusting(&key), "Mods", Some(&manifest), Some(&sig), &actual, no_key).unwrap();
+        assert_eq!(signer.as_deref(), Some("Anna"));
+        // Our own pushes need no entry in the trusted list.
+        let own = || Some(to_hex(SigningKey::from_bytes(&[7; 32]).verifying_key().as_bytes()));
+        let signer = verify_download_as(&SyncConfig::default(), "Mods", Some(&manifest), Some(&sig), &actual, own).unwrap();
+        assert_eq!(signer.as_deref(), Some("you"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn tampered_manifest_or_files_are_refused() {
+        let dir = folder("tampered");
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let config = trusting(&key);
+        let (manifest, sig) = signed_manifest_with(&key, "Save", &dir, &PathFilter::all()).unwrap();
+        let actual = build_manifest(&dir).unwrap().files;
+
+        let edited = String::from_utf8(manifest.clone()).unwrap().replace("Mod.dll", "Evil.dll");
+        let err = verify_download_as(&config, "Save", Some(edited.as_bytes()), Some(&sig), &actual, no_key).unwrap_err();
+        assert!(err.contains("signature is invalid"), "{}", err);
+
+        fs::write(dir.join("plugins/Mod.dll"), "other code").unwrap();
+        let changed = build_manifest(&dir).unwrap().files;
+        let err = verify_download_as(&config, "Save", Some(&manifest), Some(&sig), &changed, no_key).unwrap_err();
+        assert!(err.contains("does not match its signed manifest"), "{}", err);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unknown_signer_is_refused_for_mods_only() {
+        let dir = folder("untrusted");
+        let stranger = SigningKey::from_bytes(&[9; 32]);
+        let config = trusting(&SigningKey::from_bytes(&[7; 32]));
+        let (manifest, sig) = signed_manifest_with(&stranger, "Mods", &dir, &PathFilter::all()).unwrap();
+        let actual = build_manifest(&dir).unwrap().files;
+        let err = verify_download_as(&config, "Mods", Some(&manifest), Some(&sig), &actual, no_key).unwrap_err();
+        assert!(err.contains("unknown key"), "{}", err);
+        let err = verify_download_as(&config, "Mods", Some(&manifest), None, &actual, no_key).unwrap_err();
+        assert!(err.contains("not signed"), "{}", err);
+
+        let allowing = SyncConfig { allow_unsigned_mods: true, ..config.clone() };
+        assert_eq!(verify_download_as(&allowing, "Mods", Some(&manifest), Some(&sig), &actual, no_key), Ok(None));
+        let (save, save_sig) = signed_manifest_with(&stranger, "Save", &dir, &PathFilter::all()).unwrap();
+        assert_eq!(verify_download_as(&config, "Save", Some(&save), Some(&save_sig), &actual, no_key), Ok(None));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn manifest_is_bound_to_its_cloud_name() {
+        let dir = folder("binding");
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let config = trusting(&key);
+        let (manifest, sig) = signed_manifest_with(&key, "mod_profiles/coop/Mods", &dir, &PathFilter::all()).unwrap();
+        let actual = build_manifest(&dir).unwrap().files;
+        assert!(verify_download_as(&config, "mod_profiles/coop/Mods", Some(&manifest), Some(&sig), &actual, no_key).is_ok());
+        for other in ["Mods", "mod_profiles/solo/Mods", "Save"] {
+            let err = verify_download_as(&config, other, Some(&manifest), Some(&sig), &actual, no_key).unwrap_err();
+            assert!(err.contains("was pushed as mod_profiles/coop/Mods"), "{}", err);
+        }
+
+        // Signed before the binding existed: only good where unsigned copies are.
+        let unbound = serde_json::to_vec_pretty(&build_manifest(&dir).unwrap()).unwrap();
+        let unbound_sig = super::sign_with(&key, &unbound).unwrap();
+        let err = verify_download_as(&config, "Mods", Some(&unbound), Some(&unbound_sig), &actual, no_key).unwrap_err();
+        assert!(err.contains("does not name its profile"), "{}", err);
+        assert!(verify_download_as(&config, "Save", Some(&unbound), Some(&unbound_sig), &actual, no_key).is_ok());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,10 +1,15 @@
 //! Supabase Storage: upload/download Save.zip and Mods.zip.
 
-use crate::sync::{clear_dir, get_latest_mtime_recursive, set_synced_organisation_name, SyncConfig, SyncTarget};
+use crate::archive::{manifest_of_zip, unzip_to_dir, zip_dir};
+use crate::crypto::{open_downloaded, seal_for_upload};
+use crate::signing::{signed_manifest, verify_download};
+use crate::filter::PathFilter;
+use crate::manifest::ManifestEntry;
+use crate::sync::{latest_mtime, local_target, set_synced_organisation_name, Overrides, SyncConfig, SyncTarget};
 use serde::Deserialize;
 use std::fs;
 use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 /// Money.json structure (we only need LifetimeEarnings for progress check).
 #[derive(Deserialize)]
@@ -15,7 +20,7 @@ struct MoneyData {
 
 /// Returns the maximum LifetimeEarnings found in any SaveGame_*/Money.json under the save root.
 /// Used to prevent uploading a save that is behind the cloud (progress can't go backwards).
-fn max_lifetime_earnings_from_save_dir(save_root: &Path) -> Option<f64> {
+pub(crate) fn max_lifetime_earnings_from_save_dir(save_root: &Path) -> Option<f64> {
     // Also check if Money.json exists directly in save_root (flat save structure).
     if let Ok(bytes) = fs::read(save_root.join("Money.json")) {
         if let Ok(data) = serde_json::from_slice::<MoneyData>(&bytes) {
@@ -63,69 +68,20 @@ fn system_time_to_unix(t: SystemTime) -> Option<i64> {
     t.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs() as i64)
 }
 
-/// Zip en mappe rekursivt til bytes.
-fn zip_dir(path: &Path) -> Result<Vec<u8>, String> {
-    let mut buf = Vec::new();
-    {
-        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut buf));
-        let options = zip::write::SimpleFileOptions::default()
-            .unix_permissions(0o755)
-            .compression_method(zip::CompressionMethod::Deflated);
-
-        fn add_dir<W: std::io::Write + std::io::Seek>(
-            zip: &mut zip::ZipWriter<W>,
-            options: zip::write::SimpleFileOptions,
-            dir: &Path,
-            prefix: &str,
-        ) -> Result<(), String> {
-            for e in fs::read_dir(dir).map_err(|e| e.to_string())? {
-                let e = e.map_err(|e| e.to_string())?;
-                let name = e.file_name();
-                let full = dir.join(&name);
-                let entry_path = if prefix.is_empty() {
-                    name.to_string_lossy().to_string()
-                } else {
-                    format!("{}/{}", prefix, name.to_string_lossy())
-                };
-                if e.file_type().map_err(|e| e.to_string())?.is_dir() {
-                    add_dir(zip, options, &full, &entry_path)?;
-                } else {
-                    zip.start_file(&entry_path, options)
-                        .map_err(|e| e.to_string())?;
-                    let mut f = fs::File::open(&full).map_err(|e| e.to_string())?;
-                    std::io::copy(&mut f, zip).map_err(|e| e.to_string())?;
-                }
-            }
-            Ok(())
-        }
-
-        if path.is_dir() {
-            add_dir(&mut zip, options, path, "")?;
-        }
-        zip.finish().map_err(|e| e.to_string())?;
+/// Log a storage request that got an answer: status code and timing.
+fn log_response(op: &str, object: &str, status: reqwest::StatusCode, started: Instant, bytes: Option<usize>) {
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    if status.is_success() {
+        tracing::debug!(op, object, status = status.as_u16(), elapsed_ms, bytes, "supabase request");
+    } else {
+        tracing::warn!(op, object, status = status.as_u16(), elapsed_ms, "supabase request failed");
     }
-    Ok(buf)
 }
 
-/// Udpak zip-bytes til en mappe (mappen tømmes først).
-fn unzip_to_dir(bytes: &[u8], dest: &Path) -> Result<(), String> {
-    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).map_err(|e| e.to_string())?;
-    clear_dir(dest).map_err(|e| e.to_string())?;
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i).map_err(|e| e.to_string())?;
-        let name = file.name().to_string();
-        let out = dest.join(&name);
-        if file.is_dir() {
-            fs::create_dir_all(&out).map_err(|e| e.to_string())?;
-        } else {
-            if let Some(p) = out.parent() {
-                fs::create_dir_all(p).map_err(|e| e.to_string())?;
-            }
-            let mut out_file = fs::File::create(&out).map_err(|e| e.to_string())?;
-            std::io::copy(&mut file, &mut out_file).map_err(|e| e.to_string())?;
-        }
-    }
-    Ok(())
+/// Log a storage request that got no answer (offline, DNS, TLS) and turn it into our error type.
+fn http_error(op: &str, object: &str, e: reqwest::Error) -> String {
+    tracing::warn!(op, object, error = %e, "supabase request did not complete");
+    e.to_string()
 }
 
 fn supabase_client() -> Result<reqwest::blocking::Client, String> {
@@ -134,7 +90,7 @@ fn supabase_client() -> Result<reqwest::blocking::Client, String> {
         .map_err(|e| e.to_string())
 }
 
-fn supabase_upload(
+pub(crate) fn supabase_upload(
     url: &str,
     key: &str,
     bucket: &str,
@@ -143,6 +99,7 @@ fn supabase_upload(
 ) -> Result<(), String> {
     let url = url.trim_end_matches('/');
     let endpoint = format!("{}/storage/v1/object/{}/{}", url, bucket, object_name);
+    let started = Instant::now();
     let res = supabase_client()?
         .post(&endpoint)
         .header("Authorization", format!("Bearer {}", key))
@@ -151,7 +108,8 @@ fn supabase_upload(
         .header("x-upsert", "true")
         .body(data.to_vec())
         .send()
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| http_error("upload", object_name, e))?;
+    log_response("upload", object_name, res.status(), started, Some(data.len()));
     if !res.status().is_success() {
         let status = res.status();
         let body = res.text().unwrap_or_default();
@@ -163,12 +121,14 @@ fn supabase_upload(
 fn supabase_download(url: &str, key: &str, bucket: &str, object_name: &str) -> Result<Vec<u8>, String> {
     let url = url.trim_end_matches('/');
     let endpoint = format!("{}/storage/v1/object/{}/{}", url, bucket, object_name);
+    let started = Instant::now();
     let res = supabase_client()?
         .get(&endpoint)
         .header("Authorization", format!("Bearer {}", key))
         .header("apikey", key)
         .send()
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| http_error("download", object_name, e))?;
+    log_response("download", object_name, res.status(), started, None);
     if !res.status().is_success() {
         return Err(format!("Download failed: {}", res.status()));
     }
@@ -176,83 +136,243 @@ fn supabase_download(url: &str, key: &str, bucket: &str, object_name: &str) -> R
     Ok(bytes.to_vec())
 }
 
-#[derive(Deserialize)]
-struct ListObject {
-    name: String,
-    updated_at: Option<String>,
+/// Like `supabase_download`, but a missing object is `Ok(None)` instead of an error.
+/// Storage answers 400 (not 404) for unknown objects, so both count as missing.
+pub(crate) fn supabase_try_download(
+    url: &str,
+    key: &str,
+    bucket: &str,
+    object_name: &str,
+) -> Result<Option<Vec<u8>>, String> {
+    let url = url.trim_end_matches('/');
+    let endpoint = format!("{}/storage/v1/object/{}/{}", url, bucket, object_name);
+    let started = Instant::now();
+    let res = supabase_client()?
+        .get(&endpoint)
+        .header("Authorization", format!("Bearer {}", key))
+        .header("apikey", key)
+        .send()
+        .map_err(|e| http_error("download", object_name, e))?;
+    let status = res.status();
+    log_response("download", object_name, status, started, None);
+    if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::BAD_REQUEST {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(format!("Download failed: {}", status));
+    }
+    let bytes = res.bytes().map_err(|e| e.to_string())?;
+    Ok(Some(bytes.to_vec()))
 }
 
-fn supabase_list(url: &str, key: &str, bucket: &str) -> Result<Vec<ListObject>, String> {
+/// Delete objects by name (e.g. unreferenced blobs).
+pub(crate) fn supabase_delete(url: &str, key: &str, bucket: &str, object_names: &[String]) -> Result<(), String> {
+    if object_names.is_empty() {
+        return Ok(());
+    }
     let url = url.trim_end_matches('/');
-    let endpoint = format!("{}/storage/v1/object/list/{}", url, bucket);
+    let endpoint = format!("{}/storage/v1/object/{}", url, bucket);
     let res = supabase_client()?
-        .post(&endpoint)
+        .delete(&endpoint)
         .header("Authorization", format!("Bearer {}", key))
         .header("apikey", key)
-        .header("Content-Type", "application/json")
-        .body(r#"{"prefix":""}"#)
+        .json(&serde_json::json!({ "prefixes": object_names }))
         .send()
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| http_error("delete", bucket, e))?;
+    tracing::debug!(objects = object_names.len(), status = %res.status(), "supabase delete");
     if !res.status().is_success() {
-        return Err(format!("List failed: {}", res.status()));
+        return Err(format!("Delete failed: {}", res.status()));
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ListObject {
+    pub(crate) name: String,
+    /// None for "folders" (common prefixes) in the listing.
+    pub(crate) id: Option<String>,
+    pub(crate) updated_at: Option<String>,
+}
+
+/// List objects directly under `prefix` ("" for the bucket root), following pagination.
+pub(crate) fn supabase_list(url: &str, key: &str, bucket: &str, prefix: &str) -> Result<Vec<ListObject>, String> {
+    const PAGE: usize = 1000;
+    let url = url.trim_end_matches('/');
+    let endpoint = format!("{}/storage/v1/object/list/{}", url, bucket);
+    let mut all = Vec::new();
+    loop {
+        let started = Instant::now();
+        let res = supabase_client()?
+            .post(&endpoint)
+            .header("Authorization", format!("Bearer {}", key))
+            .header("apikey", key)
+            .json(&serde_json::json!({ "prefix": prefix, "limit": PAGE, "offset": all.len() }))
+            .send()
+            .map_err(|e| http_error("list", prefix, e))?;
+        log_response("list", prefix, res.status(), started, None);
+        if !res.status().is_success() {
+            return Err(format!("List failed: {}", res.status()));
+        }
+        let page: Vec<ListObject> = res.json().map_err(|e| e.to_string())?;
+        let done = page.len() < PAGE;
+        all.extend(page);
+        if done {
+            return Ok(all);
+        }
     }
-    let list: Vec<ListObject> = res.json().map_err(|e| e.to_string())?;
-    Ok(list)
 }
 
-/// Returns (Save.zip updated_at unix, Mods.zip updated_at unix).
+/// Returns (updated_at unix of the Save object, of the Mods object), e.g. Save.zip and Mods.zip.
 fn supabase_object_mtimes(
+    config: &SyncConfig,
     url: &str,
     key: &str,
     bucket: &str,
 ) -> Result<(Option<i64>, Option<i64>), String> {
-    let list = supabase_list(url, key, bucket)?;
-    let mut save_ts = None;
-    let mut mods_ts = None;
-    for obj in list {
-        let ts = obj
+    let save_object = crate::blobs::cloud_object_name(config, "Save");
+    let mods_object = crate::blobs::cloud_object_name(config, &crate::profiles::cloud_name(config, "Mods"));
+    // Listings are per folder; a mod profile's objects live below the root.
+    let object_mtime = |object: &str, listing: &[ListObject]| -> Option<i64> {
+        let file = object.rsplit('/').next().unwrap_or(object);
+        listing
+            .iter()
+            .find(|o| o.name == file)?
             .updated_at
             .as_ref()
             .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
-            .map(|dt| dt.timestamp());
-        if obj.name == "Save.zip" {
-            save_ts = ts;
-        } else if obj.name == "Mods.zip" {
-            mods_ts = ts;
-        }
-    }
+            .map(|dt| dt.timestamp())
+    };
+    let root = supabase_list(url, key, bucket, "")?;
+    let save_ts = object_mtime(&save_object, &root);
+    let mods_ts = match mods_object.rsplit_once('/') {
+        Some((dir, _)) => object_mtime(&mods_object, &supabase_list(url, key, bucket, dir)?),
+        None => object_mtime(&mods_object, &root),
+    };
     Ok((save_ts, mods_ts))
 }
 
-pub fn sync_pull_supabase(config: &SyncConfig, target: SyncTarget, force: bool) -> Result<String, String> {
-    let save_path = config.save_path.as_ref().ok_or("Save path is not set")?;
-    let mods_path = config.mods_path.as_ref().ok_or("Mods path is not set")?;
+/// Highest LifetimeEarnings in the cloud save, or None if there is no cloud save (or no Money.json).
+/// In blob mode only the Money.json files are downloaded. A cloud save this PC cannot open (plain
+/// while encryption is on, another passphrase, untrusted signature) also gives None: the check
+/// has nothing to compare, and failing here would block the upload that replaces it.
+fn cloud_max_lifetime_earnings(config: &SyncConfig, url: &str, key: &str, bucket: &str) -> Result<Option<f64>, String> {
+    let temp_dir = std::env::temp_dir().join(format!(
+        "syncone_check_{}",
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+    ));
+    let _ = fs::create_dir_all(&temp_dir);
+    let fetched = if crate::blobs::uses_blobs(config, "Save") {
+        let remote = crate::blobs::Remote::from_config(config)?;
+        crate::blobs::fetch_files(config, &remote, "Save", &temp_dir, |path| {
+            path == "Money.json" || path.ends_with("/Money.json")
+        })
+    } else {
+        match supabase_download(url, key, bucket, "Save.zip") {
+            Ok(bytes) => open_downloaded(config, bytes, "Save.zip").map(|data| unzip_to_dir(&data, &temp_dir, &PathFilter::all()).is_ok()),
+            Err(_) => Ok(false),
+        }
+    };
+    let result = match fetched {
+        Ok(true) => max_lifetime_earnings_from_save_dir(&temp_dir),
+        Ok(false) => None,
+        Err(e) => {
+            tracing::warn!(error = %e, "cloud save unreadable, skipping the progress check");
+            None
+        }
+    };
+    let _ = fs::remove_dir_all(&temp_dir);
+    Ok(result)
+}
+
+/// Check the decrypted `<name>.zip` bytes against the signed manifest uploaded next to it.
+/// Returns the archive's file list.
+fn verify_supabase_archive(
+    config: &SyncConfig,
+    url: &str,
+    key: &str,
+    bucket: &str,
+    name: &str,
+    data: &[u8],
+) -> Result<Vec<ManifestEntry>, String> {
+    let fetch = |object: String| -> Result<Option<Vec<u8>>, String> {
+        match supabase_download(url, key, bucket, &object) {
+            Ok(bytes) => open_downloaded(config, bytes, &object).map(Some),
+            Err(_) => Ok(None),
+        }
+    };
+    let manifest = fetch(format!("{}.manifest.json", name))?;
+    let signature = fetch(format!("{}.manifest.sig", name))?;
+    let actual = manifest_of_zip(data)?;
+    verify_download(config, name, manifest.as_deref(), signature.as_deref(), &actual)?;
+    Ok(actual)
+}
+
+/// Upload `<name>.manifest.json` and its detached signature for the folder just archived.
+fn upload_signed_manifest(
+    config: &SyncConfig,
+    url: &str,
+    key: &str,
+    bucket: &str,
+    name: &str,
+    dir: &Path,
+    filter: &PathFilter,
+) -> Result<(), String> {
+    let (manifest_bytes, sig_bytes) = signed_manifest(name, dir, filter)?;
+    let sig_object = format!("{}.manifest.sig", name);
+    supabase_upload(url, key, bucket, &sig_object, &seal_for_upload(config, sig_bytes)?)?;
+    let manifest_object = format!("{}.manifest.json", name);
+    supabase_upload(url, key, bucket, &manifest_object, &seal_for_upload(config, manifest_bytes)?)
+}
+
+pub fn sync_pull_supabase(config: &SyncConfig, target: SyncTarget, overrides: Overrides) -> Result<String, String> {
+    let (local_save, save_filter) = local_target(config, "Save")?;
+    let (local_mods, mods_filter) = local_target(config, "Mods")?;
     let url = config.supabase_url.as_ref().ok_or("Supabase URL is missing")?;
     let key = config.supabase_key.as_ref().ok_or("Supabase key is missing")?;
     let bucket = config.bucket_name.as_ref().ok_or("Bucket name is missing")?;
 
-    let local_save = Path::new(save_path);
-    let local_mods = Path::new(mods_path);
+    let local_save = local_save.as_path();
+    let local_mods = local_mods.as_path();
     let mut messages = Vec::new();
 
-    let (save_ts, mods_ts) = supabase_object_mtimes(url, key, bucket)?;
+    let (save_ts, mods_ts) = supabase_object_mtimes(config, url, key, bucket)?;
 
     if target == SyncTarget::Save || target == SyncTarget::Both {
-        let local_save_mtime = local_save
-            .exists()
-            .then(|| get_latest_mtime_recursive(local_save).ok())
-            .flatten()
-            .and_then(system_time_to_unix);
+        let local_save_mtime = latest_mtime(local_save, &save_filter).and_then(system_time_to_unix);
         if let Some(cloud_ts) = save_ts {
             let should_pull = match local_save_mtime {
-                Some(local) => cloud_ts > local,
+                Some(local) => config.refetch_save || cloud_ts > local,
                 None => true,
             };
-            if should_pull {
-                let data = supabase_download(url, key, bucket, "Save.zip")?;
+            crate::sync::log_pull_decision("Save", Some(cloud_ts), local_save_mtime, should_pull);
+            if should_pull && !overrides.ignore_setup_mismatch {
+                crate::save_meta::check_save_meta(config, target == SyncTarget::Both)?;
+            }
+            if should_pull && crate::blobs::uses_blobs(config, "Save") {
+                if !overrides.ignore_progress {
+                    let cloud_max = cloud_max_lifetime_earnings(config, url, key, bucket)?;
+                    let local_max = max_lifetime_earnings_from_save_dir(local_save);
+                    if let (Some(local_val), Some(cloud_val)) = (local_max, cloud_max) {
+                        if local_val > cloud_val {
+                            return Err(crate::sync::fetch_progress_warning(local_val, cloud_val));
+                        }
+                    }
+                }
+                let remote = crate::blobs::Remote::from_config(config)?;
+                if crate::blobs::pull_dir(config, &remote, "Save", local_save, &save_filter)? {
+                    crate::sync::inject_has_exited_rv(local_save)?;
+                    set_synced_organisation_name(local_save)?;
+                    messages.push("Save fetched from Supabase.");
+                }
+            } else if should_pull {
+                let data = open_downloaded(config, supabase_download(url, key, bucket, "Save.zip")?, "Save.zip")?;
+                verify_supabase_archive(config, url, key, bucket, "Save", &data)?;
 
-                // Unless force, check if local save is more advanced than the cloud version.
-                if !force {
+                // Unless overridden, check if local save is more advanced than the cloud version.
+                if !overrides.ignore_progress {
                     let temp_dir = std::env::temp_dir().join(format!(
                         "syncone_pull_check_{}",
                         SystemTime::now()
@@ -261,16 +381,13 @@ pub fn sync_pull_supabase(config: &SyncConfig, target: SyncTarget, force: bool)
                             .as_millis()
                     ));
                     let _ = fs::create_dir_all(&temp_dir);
-                    if unzip_to_dir(&data, &temp_dir).is_ok() {
+                    if unzip_to_dir(&data, &temp_dir, &PathFilter::all()).is_ok() {
                         let cloud_max = max_lifetime_earnings_from_save_dir(&temp_dir);
                         let local_max = max_lifetime_earnings_from_save_dir(local_save);
                         let _ = fs::remove_dir_all(&temp_dir);
                         if let (Some(local_val), Some(cloud_val)) = (local_max, cloud_max) {
                             if local_val > cloud_val {
-                                return Err(format!(
-                                    "PROGRESS_WARNING:The cloud save appears to be behind your local save.\n\nYour lifetime earnings: {:.0}\nCloud lifetime earnings: {:.0}\n\nFetching would overwrite your more advanced local save.",
-                                    local_val, cloud_val
-                                ));
+                                return Err(crate::sync::fetch_progress_warning(local_val, cloud_val));
                             }
                         }
                     } else {
@@ -278,7 +395,7 @@ pub fn sync_pull_supabase(config: &SyncConfig, target: SyncTarget, force: bool)
                     }
                 }
 
-                unzip_to_dir(&data, local_save)?;
+                unzip_to_dir(&data, local_save, &save_filter)?;
                 crate::sync::inject_has_exited_rv(local_save)?;
                 set_synced_organisation_name(local_save)?;
                 messages.push("Save fetched from Supabase.");
@@ -287,20 +404,28 @@ pub fn sync_pull_supabase(config: &SyncConfig, target: SyncTarget, force: bool)
     }
 
     if target == SyncTarget::Mods || target == SyncTarget::Both {
-        let local_mods_mtime = local_mods
-            .exists()
-            .then(|| get_latest_mtime_recursive(local_mods).ok())
-            .flatten()
-            .and_then(system_time_to_unix);
+        let mods_name = crate::profiles::cloud_name(config, "Mods");
+        let local_mods_mtime = latest_mtime(local_mods, &mods_filter).and_then(system_time_to_unix);
         if let Some(cloud_ts) = mods_ts {
             let should_pull = match local_mods_mtime {
-                Some(local) => cloud_ts > local,
+                Some(local) => config.refetch_mods || cloud_ts > local,
                 None => true,
             };
+            crate::sync::log_pull_decision(&mods_name, Some(cloud_ts), local_mods_mtime, should_pull);
             if should_pull {
-                let data = supabase_download(url, key, bucket, "Mods.zip")?;
-                unzip_to_dir(&data, local_mods)?;
-                messages.push("Mods fetched from Supabase.");
+                if crate::blobs::uses_blobs(config, &mods_name) {
+                    let remote = crate::blobs::Remote::from_config(config)?;
+                    if crate::blobs::pull_dir(config, &remote, &mods_name, local_mods, &mods_filter)? {
+                        messages.push("Mods fetched from Supabase.");
+                    }
+                } else {
+                    let zip_object = format!("{}.zip", mods_name);
+                    let data = open_downloaded(config, supabase_download(url, key, bucket, &zip_object)?, &zip_object)?;
+                    let actual = verify_supabase_archive(config, url, key, bucket, &mods_name, &data)?;
+                    mods_filter.check_layout(&mods_name, actual.iter().map(|e| e.path.as_str()))?;
+                    unzip_to_dir(&data, local_mods, &mods_filter)?;
+                    messages.push("Mods fetched from Supabase.");
+                }
             }
         }
     }
@@ -312,57 +437,55 @@ pub fn sync_pull_supabase(config: &SyncConfig, target: SyncTarget, force: bool)
     }
 }
 
-pub fn sync_push_supabase(config: &SyncConfig, target: SyncTarget, force: bool) -> Result<String, String> {
-    let save_path = config.save_path.as_ref().ok_or("Save path is not set")?;
-    let mods_path = config.mods_path.as_ref().ok_or("Mods path is not set")?;
+pub fn sync_push_supabase(config: &SyncConfig, target: SyncTarget, overrides: Overrides) -> Result<String, String> {
+    let (local_save, save_filter) = local_target(config, "Save")?;
+    let (local_mods, mods_filter) = local_target(config, "Mods")?;
     let url = config.supabase_url.as_ref().ok_or("Supabase URL is missing")?;
     let key = config.supabase_key.as_ref().ok_or("Supabase key is missing")?;
     let bucket = config.bucket_name.as_ref().ok_or("Bucket name is missing")?;
 
-    let local_save = Path::new(save_path);
-    let local_mods = Path::new(mods_path);
+    let local_save = local_save.as_path();
+    let local_mods = local_mods.as_path();
     let mut messages = Vec::new();
 
     if (target == SyncTarget::Save || target == SyncTarget::Both) && local_save.exists() {
-        // Unless force=true, check LifetimeEarnings to prevent uploading a save that is behind the cloud.
-        if !force {
-        if let Ok(cloud_bytes) = supabase_download(url, key, bucket, "Save.zip") {
-            let temp_dir = std::env::temp_dir().join(format!(
-                "syncone_check_{}",
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_millis()
-            ));
-            let _ = fs::create_dir_all(&temp_dir);
-            if unzip_to_dir(&cloud_bytes, &temp_dir).is_ok() {
-                let cloud_max = max_lifetime_earnings_from_save_dir(&temp_dir);
-                let local_max = max_lifetime_earnings_from_save_dir(local_save);
-                let _ = fs::remove_dir_all(&temp_dir);
-                if let (Some(local_val), Some(cloud_val)) = (local_max, cloud_max) {
-                    if local_val < cloud_val {
-                        return Err(format!(
-                            "PROGRESS_WARNING:Your local save appears to be behind the cloud version.\n\nYour lifetime earnings: {:.0}\nCloud lifetime earnings: {:.0}\n\nUploading would overwrite a more advanced save.",
-                            local_val, cloud_val
-                        ));
-                    }
+        // Unless overridden, check LifetimeEarnings to prevent uploading a save that is behind the cloud.
+        if !overrides.ignore_progress {
+            let cloud_max = cloud_max_lifetime_earnings(config, url, key, bucket)?;
+            let local_max = max_lifetime_earnings_from_save_dir(local_save);
+            if let (Some(local_val), Some(cloud_val)) = (local_max, cloud_max) {
+                if local_val < cloud_val {
+                    return Err(crate::sync::upload_progress_warning(local_val, cloud_val));
                 }
-            } else {
-                let _ = fs::remove_dir_all(&temp_dir);
             }
         }
-        } // end if !force
 
         // Stamp the save name so it's easy to recognize in-game.
         let _ = set_synced_organisation_name(local_save);
 
-        let data = zip_dir(local_save)?;
-        supabase_upload(url, key, bucket, "Save.zip", &data)?;
+        if crate::blobs::uses_blobs(config, "Save") {
+            let remote = crate::blobs::Remote::from_config(config)?;
+            crate::blobs::push_dir(config, &remote, "Save", local_save, &save_filter)?;
+        } else {
+            let data = seal_for_upload(config, zip_dir(local_save, &save_filter)?)?;
+            supabase_upload(url, key, bucket, "Save.zip", &data)?;
+            upload_signed_manifest(config, url, key, bucket, "Save", local_save, &save_filter)?;
+        }
+        crate::push_meta::upload_push_meta(config, "Save", local_save, &save_filter)?;
         messages.push("Save uploaded to Supabase.");
     }
     if (target == SyncTarget::Mods || target == SyncTarget::Both) && local_mods.exists() {
-        let data = zip_dir(local_mods)?;
-        supabase_upload(url, key, bucket, "Mods.zip", &data)?;
+        let mods_name = crate::profiles::cloud_name(config, "Mods");
+        if crate::blobs::uses_blobs(config, &mods_name) {
+            let remote = crate::blobs::Remote::from_config(config)?;
+            crate::blobs::push_dir(config, &remote, &mods_name, local_mods, &mods_filter)?;
+        } else {
+            let data = seal_for_upload(config, zip_dir(local_mods, &mods_filter)?)?;
+            supabase_upload(url, key, bucket, &format!("{}.zip", mods_name), &data)?;
+            upload_signed_manifest(config, url, key, bucket, &mods_name, local_mods, &mods_filter)?;
+        }
+        crate::inventory::upload_inventory(config, local_mods, &mods_filter)?;
+        crate::push_meta::upload_push_meta(config, &mods_name, local_mods, &mods_filter)?;
         messages.push("Mods uploaded to Supabase.");
     }
 
@@ -385,9 +508,11 @@ pub fn get_sync_status_supabase(config: &SyncConfig) -> crate::sync::SyncStatus
         mods_cloud_newer: false,
         save_path_used: None,
         mods_path_used: None,
+        save_last_push: None,
+        mods_last_push: None,
     };
-    let (save_path, mods_path) = match (config.save_path.as_deref(), config.mods_path.as_deref()) {
-        (Some(s), Some(m)) => (Path::new(s), Path::new(m)),
+    let ((save_path, save_filter), (mods_path, mods_filter)) = match (local_target(config, "Save"), local_target(config, "Mods")) {
+        (Ok(s), Ok(m)) => (s, m),
         _ => return status,
     };
     status.save_path_used = Some(save_path.to_string_lossy().to_string());
@@ -402,18 +527,10 @@ pub fn get_sync_status_supabase(config: &SyncConfig) -> crate::sync::SyncStatus
         _ => return status,
     };
 
-    status.save_local_mtime = save_path
-        .exists()
-        .then(|| get_latest_mtime_recursive(save_path).ok())
-        .flatten()
-        .and_then(system_time_to_unix);
-    status.mods_local_mtime = mods_path
-        .exists()
-        .then(|| get_latest_mtime_recursive(mods_path).ok())
-        .flatten()
-        .and_then(system_time_to_unix);
-
-    let (save_cloud_ts, mods_cloud_ts) = match supabase_object_mtimes(url, key, bucket) {
+    status.save_local_mtime = latest_mtime(&save_path, &save_filter).and_then(system_time_to_unix);
+    status.mods_local_mtime = latest_mtime(&mods_path, &mods_filter).and_then(system_time_to_unix);
+
+    let (save_cloud_ts, mods_cloud_ts) = match supabase_object_mtimes(config, url, key, bucket) {
         Ok(t) => t,
         Err(_) => return status,
     };
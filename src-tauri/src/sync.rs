@@ -4,8 +4,14 @@ use serde::{Deserialize, Serialize};
 use chrono::Local;
 use std::io::Write;
 use std::fs;
-use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::filter::{PathFilter, DEFAULT_MOD_EXCLUDES, DEFAULT_MOD_FOLDERS, DEFAULT_SAVE_EXCLUDES};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, TryLockError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long pull waits for the desktop sync client (Drive/OneDrive) to finish downloading a cloud folder.
+const CLOUD_SETTLE_TIMEOUT: Duration = Duration::from_secs(10);
+const CLOUD_SETTLE_POLL: Duration = Duration::from_secs(2);
 
 /// Which part to sync: save only, mods only, or both.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -16,12 +22,113 @@ pub enum SyncTarget {
     Both,
 }
 
+/// Warnings the user chose to go past for one fetch or upload. Each check has its own flag, so
+/// confirming one warning never skips the others.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Overrides {
+    /// Sync although the game is running (`GAME_RUNNING:`)
+    pub allow_game_running: bool,
+    /// Overwrite a save with higher lifetime earnings (`PROGRESS_WARNING:`)
+    pub ignore_progress: bool,
+    /// Fetch a save last played on another game build or mod set (`SAVE_MISMATCH:`)
+    pub ignore_setup_mismatch: bool,
+}
+
+impl Overrides {
+    /// Whether any warning was gone past, for the activity log.
+    pub fn any(&self) -> bool {
+        self.allow_game_running || self.ignore_progress || self.ignore_setup_mismatch
+    }
+}
+
+/// Prefix of the error returned when a fetch or upload would overwrite a save with higher
+/// lifetime earnings; the UI offers to go ahead anyway (`Overrides::ignore_progress`).
+pub(crate) const PROGRESS_WARNING_PREFIX: &str = "PROGRESS_WARNING:";
+
+/// A fetch would overwrite the more advanced local save.
+pub(crate) fn fetch_progress_warning(local: f64, cloud: f64) -> String {
+    format!(
+        "{}The cloud save appears to be behind your local save.\n\nYour lifetime earnings: {:.0}\nCloud lifetime earnings: {:.0}\n\nFetching would overwrite your more advanced local save.",
+        PROGRESS_WARNING_PREFIX, local, cloud
+    )
+}
+
+/// An upload would overwrite the more advanced cloud save.
+pub(crate) fn upload_progress_warning(local: f64, cloud: f64) -> String {
+    format!(
+        "{}Your local save appears to be behind the cloud version.\n\nYour lifetime earnings: {:.0}\nCloud lifetime earnings: {:.0}\n\nUploading would overwrite a more advanced save.",
+        PROGRESS_WARNING_PREFIX, local, cloud
+    )
+}
+
+/// Held while a fetch, upload, restore or profile switch runs.
+static SYNC_RUNNING: Mutex<()> = Mutex::new(());
+
+/// Claim the sync for this process: the window, the tray and the fetch at login never work on the
+/// same folders at once. Hold the guard until the operation is done.
+pub(crate) fn sync_lock() -> Result<MutexGuard<'static, ()>, String> {
+    match SYNC_RUNNING.try_lock() {
+        Ok(guard) => Ok(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Ok(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => Err("A sync is already running".to_string()),
+    }
+}
+
+/// Which events show a desktop notification (all on by default).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Fetch / upload finished or failed while the window was not in front
+    pub sync_results: bool,
+    /// A fetch or upload stopped by a warning (progress, game running, setup mismatch)
+    pub warnings: bool,
+    /// Handoffs to you and changes of host
+    pub handoffs: bool,
+    /// Someone else pushed a new save or mods
+    pub cloud_changes: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings { sync_results: true, warnings: true, handoffs: true, cloud_changes: true }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct SyncConfig {
     /// Path to the Schedule I save folder (e.g. .../Saves/ID)
     pub save_path: Option<String>,
     /// Path to the Schedule I mods folder
     pub mods_path: Option<String>,
+    /// Schedule I install folder (next to the game executable). When set, the mods target is
+    /// `mod_folders` inside it instead of `mods_path`.
+    #[serde(default)]
+    pub game_root: Option<String>,
+    /// Game executable that blocks sync while running (default "Schedule I.exe", empty = no check)
+    #[serde(default)]
+    pub game_exe: Option<String>,
+    /// Subfolders of `game_root` synced as mods (default Mods, Plugins, UserLibs, UserData)
+    #[serde(default)]
+    pub mod_folders: Vec<String>,
+    /// Glob patterns the mods target is limited to (empty = everything)
+    #[serde(default)]
+    pub mod_includes: Vec<String>,
+    /// Glob patterns left out of the mods target (default: logs, temp files, crash dumps, caches, MelonPreferences.cfg)
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub mod_excludes: Vec<String>,
+    /// Glob patterns the save target is limited to (empty = everything)
+    #[serde(default)]
+    pub save_includes: Vec<String>,
+    /// Glob patterns left out of the save target (default: temp, backup and log files)
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub save_excludes: Vec<String>,
+    /// Paths under the save folder that stay on this PC: never uploaded, overwritten or deleted
+    #[serde(default)]
+    pub save_local_only: Vec<String>,
+    /// Paths under the mods target that stay on this PC (e.g. `UserData/MyMod/keybinds.cfg`)
+    #[serde(default)]
+    pub mod_local_only: Vec<String>,
     /// Path to the cloud folder (e.g. Google Drive) – bruges kun hvis Supabase ikke er sat
     pub cloud_path: Option<String>,
     /// Supabase project URL (fx https://xxx.supabase.co)
@@ -30,6 +137,48 @@ pub struct SyncConfig {
     pub supabase_key: Option<String>,
     /// Bucket-navn i Supabase Storage
     pub bucket_name: Option<String>,
+    /// Cloud folder mode: store Save.zip/Mods.zip instead of raw folder trees
+    #[serde(default)]
+    pub cloud_zip: bool,
+    /// Encrypt Save.zip/Mods.zip with the group passphrase (kept in the OS credential store, not here)
+    #[serde(default)]
+    pub encrypt_archives: bool,
+    /// Group members whose signed uploads we accept (Ed25519 public keys)
+    #[serde(default)]
+    pub trusted_signers: Vec<crate::signing::TrustedSigner>,
+    /// Install mods that are unsigned or signed by an unknown key (off by default)
+    #[serde(default)]
+    pub allow_unsigned_mods: bool,
+    /// Sync mods file-by-file through content-addressed blobs (`blobs/<sha256>`) instead of whole archives
+    #[serde(default)]
+    pub blob_store: bool,
+    /// Git repository used as backend (local path or file:// URL, preferably bare). Takes precedence over Supabase and cloud folder.
+    pub git_remote: Option<String>,
+    /// Branch to commit to in the git repository (default "main")
+    pub git_branch: Option<String>,
+    /// Active mod profile (None = the default set); see `profiles`
+    #[serde(default)]
+    pub mod_profile: Option<String>,
+    /// Name shown to the group as the pusher (None = the OS user name); see `push_meta`
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// Closing the window quits instead of leaving SyncONE running in the tray
+    #[serde(default)]
+    pub quit_on_close: bool,
+    /// Registered to start at login with `--minimized --auto-pull` (see `autostart`)
+    #[serde(default)]
+    pub run_at_startup: bool,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    /// Pull mods even if the local copy looks newer (set while switching profiles, never saved)
+    #[serde(skip)]
+    pub(crate) refetch_mods: bool,
+    /// Pull the save even if the local copy looks newer (set when accepting a handoff, never saved)
+    #[serde(skip)]
+    pub(crate) refetch_save: bool,
+    /// Note recorded with the next push (passed with the upload, never saved)
+    #[serde(skip)]
+    pub(crate) push_note: Option<String>,
 }
 
 pub(crate) fn get_latest_mtime_recursive(path: &Path) -> std::io::Result<SystemTime> {
@@ -49,30 +198,107 @@ pub(crate) fn get_latest_mtime_recursive(path: &Path) -> std::io::Result<SystemT
     Ok(latest)
 }
 
-/// Copy directory recursively. Creates destination dir. Overwrites existing files.
-fn copy_dir_all(src: &Path, dst: &Path) -> std::io::Result<()> {
-    if src.is_file() {
-        if let Some(parent) = dst.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        fs::copy(src, dst)?;
-        return Ok(());
-    }
-    fs::create_dir_all(dst)?;
+/// Copy the files of `src` that pass `filter` into `dst`, keeping their relative paths.
+/// Overwrites existing files.
+fn copy_filtered(src: &Path, dst: &Path, prefix: &str, filter: &PathFilter) -> std::io::Result<()> {
     for e in fs::read_dir(src)? {
         let e = e?;
         let name = e.file_name();
-        let src_p = src.join(&name);
-        let dst_p = dst.join(&name);
-        if e.file_type()?.is_dir() {
-            copy_dir_all(&src_p, &dst_p)?;
+        let rel = if prefix.is_empty() {
+            name.to_string_lossy().to_string()
         } else {
-            fs::copy(&src_p, &dst_p)?;
+            format!("{}/{}", prefix, name.to_string_lossy())
+        };
+        if e.file_type()?.is_dir() {
+            if filter.enters(&rel) {
+                copy_filtered(&src.join(&name), &dst.join(&name), &rel, filter)?;
+            }
+        } else if filter.matches(&rel) {
+            fs::create_dir_all(dst)?;
+            fs::copy(src.join(&name), dst.join(&name))?;
         }
     }
     Ok(())
 }
 
+/// Where `name` (Save or Mods) lives on this PC and which files under it are synced.
+pub(crate) fn local_target(config: &SyncConfig, name: &str) -> Result<(PathBuf, PathFilter), String> {
+    match name {
+        "Save" => {
+            let save_path = config.save_path.as_ref().ok_or("Save path is not set")?;
+            let excludes = or_default(&config.save_excludes, &DEFAULT_SAVE_EXCLUDES);
+            Ok((
+                PathBuf::from(save_path),
+                PathFilter::new(Vec::new(), config.save_includes.clone(), excludes)
+                    .with_local_only(&config.save_local_only),
+            ))
+        }
+        "Mods" => {
+            let includes = config.mod_includes.clone();
+            let excludes = or_default(&config.mod_excludes, &DEFAULT_MOD_EXCLUDES);
+            if let Some(game_root) = config.game_root.as_deref().filter(|r| !r.trim().is_empty()) {
+                let folders = or_default(&config.mod_folders, &DEFAULT_MOD_FOLDERS);
+                let filter = PathFilter::new(folders, includes, excludes).with_local_only(&config.mod_local_only);
+                return Ok((PathBuf::from(game_root), filter));
+            }
+            let mods_path = config.mods_path.as_ref().ok_or("Mods path is not set")?;
+            let filter = PathFilter::new(Vec::new(), includes, excludes).with_local_only(&config.mod_local_only);
+            Ok((PathBuf::from(mods_path), filter))
+        }
+        _ => Err(format!("Unknown folder: {}", name)),
+    }
+}
+
+/// A configured list, or the defaults when it is empty.
+fn or_default(configured: &[String], defaults: &[&str]) -> Vec<String> {
+    if configured.is_empty() {
+        defaults.iter().map(|p| p.to_string()).collect()
+    } else {
+        configured.to_vec()
+    }
+}
+
+/// Read `null`, which older versions saved for "use the defaults", as an empty list.
+fn null_as_empty<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Ok(Option::<Vec<String>>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Latest mtime of the synced files and folders under `root` (None if it doesn't exist).
+/// Folder mtimes count so deleting a file also makes the copy newer.
+pub(crate) fn latest_mtime(root: &Path, filter: &PathFilter) -> Option<SystemTime> {
+    fn walk(dir: &Path, prefix: &str, filter: &PathFilter, latest: &mut SystemTime) {
+        let Ok(entries) = fs::read_dir(dir) else { return };
+        for e in entries.flatten() {
+            let name = e.file_name();
+            let rel = if prefix.is_empty() {
+                name.to_string_lossy().to_string()
+            } else {
+                format!("{}/{}", prefix, name.to_string_lossy())
+            };
+            let Ok(meta) = e.metadata() else { continue };
+            let counted = if meta.is_dir() { filter.enters(&rel) } else { filter.matches(&rel) };
+            if !counted {
+                continue;
+            }
+            *latest = (*latest).max(meta.modified().unwrap_or(SystemTime::UNIX_EPOCH));
+            if meta.is_dir() {
+                walk(&e.path(), &rel, filter, latest);
+            }
+        }
+    }
+    let meta = fs::metadata(root).ok()?;
+    // The root itself only counts when it is the synced folder, not e.g. the whole game folder.
+    let mut latest = if filter.covers_root() {
+        meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)
+    } else {
+        SystemTime::UNIX_EPOCH
+    };
+    if meta.is_dir() {
+        walk(root, "", filter, &mut latest);
+    }
+    Some(latest)
+}
+
 /// Writes HasExitedRV.json into each SaveGame_*/Variables/ under save_root.
 /// Helps avoid Schedule I "stuck on Syncing" for non-hosts when loading a synced save (community fix).
 pub(crate) fn inject_has_exited_rv(save_root: &Path) -> Result<(), String> {
@@ -145,50 +371,231 @@ pub(crate) fn clear_dir(path: &Path) -> std::io::Result<()> {
     Ok(())
 }
 
-/// Copy content of src into dst (dst is cleared first if it exists). So "merge" is: clear dst, then copy src -> dst.
-fn replace_dir_with(src: &Path, dst: &Path) -> std::io::Result<()> {
+/// Delete the files under `root` that pass `filter` (and folders left empty), leaving the rest.
+pub(crate) fn clear_matching(root: &Path, filter: &PathFilter) -> std::io::Result<()> {
+    fn walk(dir: &Path, prefix: &str, filter: &PathFilter) -> std::io::Result<()> {
+        for e in fs::read_dir(dir)? {
+            let e = e?;
+            let name = e.file_name();
+            let rel = if prefix.is_empty() {
+                name.to_string_lossy().to_string()
+            } else {
+                format!("{}/{}", prefix, name.to_string_lossy())
+            };
+            let path = dir.join(&name);
+            if e.file_type()?.is_dir() {
+                if filter.enters(&rel) {
+                    walk(&path, &rel, filter)?;
+                    if filter.may_remove_dir(&rel) {
+                        // Fails (and is kept) if something unsynced is still inside.
+                        let _ = fs::remove_dir(&path);
+                    }
+                }
+            } else if filter.matches(&rel) {
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+    if root.exists() {
+        walk(root, "", filter)?;
+    }
+    fs::create_dir_all(root)
+}
+
+/// Replace the synced files in dst with those from src; files the filter leaves out are untouched on both sides.
+pub(crate) fn replace_dir_with(src: &Path, dst: &Path, filter: &PathFilter) -> std::io::Result<()> {
     if !src.exists() {
         return Ok(());
     }
-    clear_dir(dst)?;
+    clear_matching(dst, filter)?;
     if src.is_dir() {
-        for e in fs::read_dir(src)? {
-            let e = e?;
-            let name = e.file_name();
-            copy_dir_all(&src.join(&name), &dst.join(&name))?;
-        }
+        copy_filtered(src, dst, "", filter)?;
     }
     Ok(())
 }
 
-pub fn sync_pull(config: &SyncConfig, target: SyncTarget, force: bool) -> Result<String, String> {
+/// Make dst (a cloud or repository copy) contain exactly the synced files of src.
+pub(crate) fn mirror_dir(src: &Path, dst: &Path, filter: &PathFilter) -> std::io::Result<()> {
+    clear_dir(dst)?;
+    copy_filtered(src, dst, "", filter)
+}
+
+/// Completion marker written last on push, e.g. `Save.manifest.json` next to the `Save` folder.
+fn cloud_marker_path(cloud_root: &Path, name: &str) -> std::path::PathBuf {
+    cloud_root.join(format!("{}.manifest.json", name))
+}
+
+/// Detached signature over the completion marker, e.g. `Save.manifest.sig`.
+fn cloud_signature_path(cloud_root: &Path, name: &str) -> std::path::PathBuf {
+    cloud_root.join(format!("{}.manifest.sig", name))
+}
+
+/// A plain `name` folder from before completion markers: nothing else was ever written next to
+/// it. (A newer push only removes the marker; the signature and record of the last push stay.)
+/// The next push adds the marker.
+fn is_legacy_cloud_copy(cloud_root: &Path, name: &str) -> bool {
+    cloud_root.join(name).is_dir()
+        && !cloud_marker_path(cloud_root, name).exists()
+        && !cloud_signature_path(cloud_root, name).exists()
+        && !cloud_root.join(crate::push_meta::meta_object(name)).exists()
+}
+
+/// Wait until the completion marker for `name` exists and every file it lists has arrived.
+/// Errors with "Cloud copy still syncing" if that doesn't happen within CLOUD_SETTLE_TIMEOUT.
+fn wait_for_cloud_copy(cloud_root: &Path, name: &str) -> Result<(), String> {
+    if is_legacy_cloud_copy(cloud_root, name) {
+        tracing::info!(name, "cloud copy predates completion markers, taking it as is");
+        return Ok(());
+    }
+    let marker = cloud_marker_path(cloud_root, name);
+    let dir = cloud_root.join(name);
+    let started = Instant::now();
+    loop {
+        let pending = match crate::manifest::read_manifest(&marker) {
+            Some(manifest) => crate::manifest::manifest_mismatches(&dir, &manifest).len(),
+            None => usize::MAX,
+        };
+        if pending == 0 {
+            return Ok(());
+        }
+        if started.elapsed() >= CLOUD_SETTLE_TIMEOUT {
+            return Err(if pending == usize::MAX {
+                format!("Cloud copy still syncing: {} has no completion marker yet. Wait for Google Drive/OneDrive to finish, or upload again from the PC that has the latest version.", name)
+            } else {
+                format!("Cloud copy still syncing: {} file(s) in {} are not downloaded yet. Try again when Google Drive/OneDrive has finished syncing.", pending, name)
+            });
+        }
+        std::thread::sleep(CLOUD_SETTLE_POLL);
+    }
+}
+
+/// Mirror `src` into the cloud folder `cloud_root/name` and write its completion marker last.
+/// The old marker is removed first so a pull never trusts a half-written copy.
+fn push_dir_with_marker(src: &Path, filter: &PathFilter, cloud_root: &Path, name: &str) -> Result<(), String> {
+    let marker = cloud_marker_path(cloud_root, name);
+    if marker.exists() {
+        fs::remove_file(&marker).map_err(|e| e.to_string())?;
+    }
+    let dst = cloud_root.join(name);
+    mirror_dir(src, &dst, filter).map_err(|e| e.to_string())?;
+    let (manifest_bytes, sig_bytes) = crate::signing::signed_manifest(name, &dst, &PathFilter::all())?;
+    crate::archive::write_file_atomic(&cloud_signature_path(cloud_root, name), &sig_bytes)?;
+    crate::archive::write_file_atomic(&marker, &manifest_bytes)
+}
+
+/// Where `name` (Save or Mods) lives in the cloud folder: its manifest when stored as blobs,
+/// `<name>.zip` in zip mode, else the `<name>` folder.
+fn cloud_entry_path(config: &SyncConfig, cloud_root: &Path, name: &str) -> std::path::PathBuf {
+    if crate::blobs::uses_blobs(config, name) || config.cloud_zip {
+        cloud_root.join(crate::blobs::cloud_object_name(config, name))
+    } else {
+        cloud_root.join(name)
+    }
+}
+
+/// Replace the synced files in `dst` with the cloud folder's copy of `name`.
+fn pull_from_cloud_folder(config: &SyncConfig, cloud_root: &Path, name: &str, dst: &Path, filter: &PathFilter) -> Result<(), String> {
+    if crate::blobs::uses_blobs(config, name) {
+        let remote = crate::blobs::Remote::Folder(cloud_root.to_path_buf());
+        return crate::blobs::pull_dir(config, &remote, name, dst, filter).map(|_| ());
+    }
+    let read_sidecar = |path: std::path::PathBuf, label: String| -> Result<Option<Vec<u8>>, String> {
+        match fs::read(&path) {
+            Ok(data) if config.cloud_zip => crate::crypto::open_downloaded(config, data, &label).map(Some),
+            Ok(data) => Ok(Some(data)),
+            Err(_) => Ok(None),
+        }
+    };
+    let manifest = read_sidecar(cloud_marker_path(cloud_root, name), format!("{}.manifest.json", name))?;
+    let signature = read_sidecar(cloud_signature_path(cloud_root, name), format!("{}.manifest.sig", name))?;
+
+    if config.cloud_zip {
+        let archive = cloud_entry_path(config, cloud_root, name);
+        let data = fs::read(&archive).map_err(|e| e.to_string())?;
+        let data = crate::crypto::open_downloaded(config, data, &format!("{}.zip", name))?;
+        let actual = crate::archive::manifest_of_zip(&data)?;
+        crate::signing::verify_download(config, name, manifest.as_deref(), signature.as_deref(), &actual)?;
+        filter.check_layout(name, actual.iter().map(|e| e.path.as_str()))?;
+        crate::archive::unzip_to_dir(&data, dst, filter)
+    } else {
+        wait_for_cloud_copy(cloud_root, name)?;
+        let src = cloud_root.join(name);
+        let actual = crate::manifest::build_manifest(&src)?.files;
+        crate::signing::verify_download(config, name, manifest.as_deref(), signature.as_deref(), &actual)?;
+        filter.check_layout(name, actual.iter().map(|e| e.path.as_str()))?;
+        replace_dir_with(&src, dst, filter).map_err(|e| e.to_string())
+    }
+}
+
+/// Upload the synced files of `src` to the cloud folder as `name`: one atomically renamed zip,
+/// or a mirrored folder tree plus marker.
+fn push_to_cloud_folder(config: &SyncConfig, src: &Path, filter: &PathFilter, cloud_root: &Path, name: &str) -> Result<(), String> {
+    if crate::blobs::uses_blobs(config, name) {
+        let remote = crate::blobs::Remote::Folder(cloud_root.to_path_buf());
+        crate::blobs::push_dir(config, &remote, name, src, filter).map(|_| ())
+    } else if config.cloud_zip {
+        let data = crate::crypto::seal_for_upload(config, crate::archive::zip_dir(src, filter)?)?;
+        let (manifest_bytes, sig_bytes) = crate::signing::signed_manifest(name, src, filter)?;
+        crate::archive::write_file_atomic(&cloud_entry_path(config, cloud_root, name), &data)?;
+        crate::archive::write_file_atomic(
+            &cloud_signature_path(cloud_root, name),
+            &crate::crypto::seal_for_upload(config, sig_bytes)?,
+        )?;
+        crate::archive::write_file_atomic(
+            &cloud_marker_path(cloud_root, name),
+            &crate::crypto::seal_for_upload(config, manifest_bytes)?,
+        )
+    } else if config.encrypt_archives {
+        Err("Encryption needs zip archives: enable \"Store as Save.zip / Mods.zip\" for the cloud folder.".to_string())
+    } else {
+        push_dir_with_marker(src, filter, cloud_root, name)
+    }
+}
+
+/// Log why a pull does or doesn't replace the local copy of `name`.
+pub(crate) fn log_pull_decision(name: &str, cloud_ts: Option<i64>, local_ts: Option<i64>, pull: bool) {
+    if pull {
+        tracing::info!(name, cloud_ts, local_ts, "cloud newer than local (or refetch), pulling");
+    } else {
+        tracing::info!(name, cloud_ts, local_ts, "local is up to date, not pulling");
+    }
+}
+
+pub fn sync_pull(config: &SyncConfig, target: SyncTarget, overrides: Overrides) -> Result<String, String> {
+    if !overrides.allow_game_running {
+        crate::game_process::ensure_game_not_running(config, "fetch")?;
+    }
+    if crate::git_sync::use_git(config) {
+        return crate::git_sync::sync_pull_git(config, target, overrides);
+    }
     if crate::supabase_sync::use_supabase(config) {
-        return crate::supabase_sync::sync_pull_supabase(config, target, force);
+        return crate::supabase_sync::sync_pull_supabase(config, target, overrides);
     }
-    let save_path = config
-        .save_path
-        .as_ref()
-        .ok_or("Save path is not set")?;
-    let mods_path = config.mods_path.as_ref().ok_or("Mods path is not set")?;
+    let (local_save, save_filter) = local_target(config, "Save")?;
+    let (local_mods, mods_filter) = local_target(config, "Mods")?;
     let cloud_path = config.cloud_path.as_ref().ok_or("Cloud path is not set")?;
 
-    let cloud_save = Path::new(cloud_path).join("Save");
-    let cloud_mods = Path::new(cloud_path).join("Mods");
-    let local_save = Path::new(save_path);
-    let local_mods = Path::new(mods_path);
+    let cloud_root = Path::new(cloud_path);
+    let mods_name = crate::profiles::cloud_name(config, "Mods");
+    let cloud_save = cloud_entry_path(config, cloud_root, "Save");
+    let cloud_mods = cloud_entry_path(config, cloud_root, &mods_name);
+    let local_save = local_save.as_path();
+    let local_mods = local_mods.as_path();
 
     let mut messages = Vec::new();
 
     if target == SyncTarget::Save || target == SyncTarget::Both {
         if cloud_save.exists() {
             let cloud_t = get_latest_mtime_recursive(&cloud_save).map_err(|e| e.to_string())?;
-            let local_t = if local_save.exists() {
-                get_latest_mtime_recursive(local_save).unwrap_or(SystemTime::UNIX_EPOCH)
-            } else {
-                SystemTime::UNIX_EPOCH
-            };
-            if cloud_t > local_t {
-                replace_dir_with(&cloud_save, local_save).map_err(|e| e.to_string())?;
+            let local_t = latest_mtime(local_save, &save_filter).unwrap_or(SystemTime::UNIX_EPOCH);
+            let pull = config.refetch_save || cloud_t > local_t;
+            log_pull_decision("Save", system_time_to_unix(cloud_t), system_time_to_unix(local_t), pull);
+            if pull {
+                if !overrides.ignore_setup_mismatch {
+                    crate::save_meta::check_save_meta(config, target == SyncTarget::Both)?;
+                }
+                pull_from_cloud_folder(config, cloud_root, "Save", local_save, &save_filter)?;
                 inject_has_exited_rv(local_save)?;
                 set_synced_organisation_name(local_save)?;
                 messages.push("Save fetched from cloud.");
@@ -199,13 +606,11 @@ pub fn sync_pull(config: &SyncConfig, target: SyncTarget, force: bool) -> Result
     if target == SyncTarget::Mods || target == SyncTarget::Both {
         if cloud_mods.exists() {
             let cloud_t = get_latest_mtime_recursive(&cloud_mods).map_err(|e| e.to_string())?;
-            let local_t = if local_mods.exists() {
-                get_latest_mtime_recursive(local_mods).unwrap_or(SystemTime::UNIX_EPOCH)
-            } else {
-                SystemTime::UNIX_EPOCH
-            };
-            if cloud_t > local_t {
-                replace_dir_with(&cloud_mods, local_mods).map_err(|e| e.to_string())?;
+            let local_t = latest_mtime(local_mods, &mods_filter).unwrap_or(SystemTime::UNIX_EPOCH);
+            let pull = config.refetch_mods || cloud_t > local_t;
+            log_pull_decision(&mods_name, system_time_to_unix(cloud_t), system_time_to_unix(local_t), pull);
+            if pull {
+                pull_from_cloud_folder(config, cloud_root, &mods_name, local_mods, &mods_filter)?;
                 messages.push("Mods fetched from cloud.");
             }
         }
@@ -218,14 +623,19 @@ pub fn sync_pull(config: &SyncConfig, target: SyncTarget, force: bool) -> Result
     }
 }
 
-fn config_file_path() -> std::io::Result<std::path::PathBuf> {
+/// The Syncone data dir (%APPDATA%\Syncone or ~/Syncone). Created if missing.
+pub(crate) fn config_dir() -> std::io::Result<std::path::PathBuf> {
     #[cfg(windows)]
     let base = std::env::var("APPDATA").map_err(|_| std::io::Error::new(std::io::ErrorKind::NotFound, "APPDATA not set"))?;
     #[cfg(not(windows))]
     let base = std::env::var("HOME").map_err(|_| std::io::Error::new(std::io::ErrorKind::NotFound, "HOME not set"))?;
     let dir = Path::new(&base).join("Syncone");
     fs::create_dir_all(&dir)?;
-    Ok(dir.join("syncone_config.json"))
+    Ok(dir)
+}
+
+fn config_file_path() -> std::io::Result<std::path::PathBuf> {
+    Ok(config_dir()?.join("syncone_config.json"))
 }
 
 pub fn load_config() -> Result<SyncConfig, String> {
@@ -255,12 +665,57 @@ pub struct SyncStatus {
     /// Path used for "Local" Save on this PC (for display)
     pub save_path_used: Option<String>,
     pub mods_path_used: Option<String>,
+    /// Who last pushed the save / mods, when and why (None if unknown)
+    pub save_last_push: Option<crate::push_meta::PushMeta>,
+    pub mods_last_push: Option<crate::push_meta::PushMeta>,
+}
+
+/// Which way the whole setup needs syncing, for the tray icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncState {
+    InSync,
+    LocalNewer,
+    CloudNewer,
+    /// One of save and mods is newer here, the other in the cloud: neither "fetch all" nor
+    /// "upload all" is safe.
+    Conflict,
+}
+
+impl SyncStatus {
+    pub fn state(&self) -> SyncState {
+        let local = self.save_local_newer || self.mods_local_newer;
+        let cloud = self.save_cloud_newer || self.mods_cloud_newer;
+        match (local, cloud) {
+            (false, false) => SyncState::InSync,
+            (true, false) => SyncState::LocalNewer,
+            (false, true) => SyncState::CloudNewer,
+            (true, true) => SyncState::Conflict,
+        }
+    }
 }
 
 pub fn get_sync_status(config: &SyncConfig) -> SyncStatus {
-    if crate::supabase_sync::use_supabase(config) {
-        return crate::supabase_sync::get_sync_status_supabase(config);
+    if crate::git_sync::use_git(config) {
+        // Reads the push records from the same fetch as the commit times.
+        return crate::git_sync::get_sync_status_git(config);
+    }
+    let mut status = if crate::supabase_sync::use_supabase(config) {
+        crate::supabase_sync::get_sync_status_supabase(config)
+    } else {
+        get_sync_status_folder(config)
+    };
+    if status.save_cloud_mtime.is_some() {
+        status.save_last_push = crate::push_meta::cloud_push_meta(config, "Save").ok().flatten();
     }
+    if status.mods_cloud_mtime.is_some() {
+        let mods_name = crate::profiles::cloud_name(config, "Mods");
+        status.mods_last_push = crate::push_meta::cloud_push_meta(config, &mods_name).ok().flatten();
+    }
+    status
+}
+
+fn get_sync_status_folder(config: &SyncConfig) -> SyncStatus {
     let mut status = SyncStatus {
         save_local_mtime: None,
         save_cloud_mtime: None,
@@ -272,36 +727,30 @@ pub fn get_sync_status(config: &SyncConfig) -> SyncStatus {
         mods_cloud_newer: false,
         save_path_used: None,
         mods_path_used: None,
+        save_last_push: None,
+        mods_last_push: None,
     };
-    let (save_path, mods_path, cloud_path) = match (
-        config.save_path.as_deref(),
-        config.mods_path.as_deref(),
+    let ((save_path, save_filter), (mods_path, mods_filter), cloud_path) = match (
+        local_target(config, "Save"),
+        local_target(config, "Mods"),
         config.cloud_path.as_deref(),
     ) {
-        (Some(s), Some(m), Some(c)) => (Path::new(s), Path::new(m), Path::new(c)),
+        (Ok(s), Ok(m), Some(c)) => (s, m, Path::new(c)),
         _ => return status,
     };
-    let cloud_save = cloud_path.join("Save");
-    let cloud_mods = cloud_path.join("Mods");
+    let cloud_save = cloud_entry_path(config, cloud_path, "Save");
+    let cloud_mods = cloud_entry_path(config, cloud_path, &crate::profiles::cloud_name(config, "Mods"));
 
     status.save_path_used = Some(save_path.to_string_lossy().to_string());
     status.mods_path_used = Some(mods_path.to_string_lossy().to_string());
 
-    status.save_local_mtime = save_path
-        .exists()
-        .then(|| get_latest_mtime_recursive(save_path).ok())
-        .flatten()
-        .and_then(system_time_to_unix);
+    status.save_local_mtime = latest_mtime(&save_path, &save_filter).and_then(system_time_to_unix);
     status.save_cloud_mtime = cloud_save
         .exists()
         .then(|| get_latest_mtime_recursive(&cloud_save).ok())
         .flatten()
         .and_then(system_time_to_unix);
-    status.mods_local_mtime = mods_path
-        .exists()
-        .then(|| get_latest_mtime_recursive(mods_path).ok())
-        .flatten()
-        .and_then(system_time_to_unix);
+    status.mods_local_mtime = latest_mtime(&mods_path, &mods_filter).and_then(system_time_to_unix);
     status.mods_cloud_mtime = cloud_mods
         .exists()
         .then(|| get_latest_mtime_recursive(&cloud_mods).ok())
@@ -335,34 +784,42 @@ pub fn save_config(config: &SyncConfig) -> Result<(), String> {
     Ok(())
 }
 
-pub fn sync_push(config: &SyncConfig, target: SyncTarget, force: bool) -> Result<String, String> {
+pub fn sync_push(config: &SyncConfig, target: SyncTarget, overrides: Overrides) -> Result<String, String> {
+    if !overrides.allow_game_running {
+        crate::game_process::ensure_game_not_running(config, "upload")?;
+    }
+    if crate::git_sync::use_git(config) {
+        return crate::git_sync::sync_push_git(config, target, overrides);
+    }
     if crate::supabase_sync::use_supabase(config) {
-        return crate::supabase_sync::sync_push_supabase(config, target, force);
+        return crate::supabase_sync::sync_push_supabase(config, target, overrides);
     }
-    let save_path = config
-        .save_path
-        .as_ref()
-        .ok_or("Save path is not set")?;
-    let mods_path = config.mods_path.as_ref().ok_or("Mods path is not set")?;
+    let (local_save, save_filter) = local_target(config, "Save")?;
+    let (local_mods, mods_filter) = local_target(config, "Mods")?;
     let cloud_path = config.cloud_path.as_ref().ok_or("Cloud path is not set")?;
 
-    let cloud_save = Path::new(cloud_path).join("Save");
-    let cloud_mods = Path::new(cloud_path).join("Mods");
-    let local_save = Path::new(save_path);
-    let local_mods = Path::new(mods_path);
+    let cloud_root = Path::new(cloud_path);
+    let local_save = local_save.as_path();
+    let local_mods = local_mods.as_path();
 
-    fs::create_dir_all(&cloud_save).map_err(|e| e.to_string())?;
-    fs::create_dir_all(&cloud_mods).map_err(|e| e.to_string())?;
+    fs::create_dir_all(cloud_root).map_err(|e| e.to_string())?;
 
     let mut messages = Vec::new();
 
     if (target == SyncTarget::Save || target == SyncTarget::Both) && local_save.exists() {
         set_synced_organisation_name(local_save)?;
-        replace_dir_with(local_save, &cloud_save).map_err(|e| e.to_string())?;
+        push_to_cloud_folder(config, local_save, &save_filter, cloud_root, "Save")?;
+        crate::push_meta::upload_push_meta(config, "Save", local_save, &save_filter)?;
         messages.push("Save uploaded to cloud.");
     }
     if (target == SyncTarget::Mods || target == SyncTarget::Both) && local_mods.exists() {
-        replace_dir_with(local_mods, &cloud_mods).map_err(|e| e.to_string())?;
+        let mods_name = crate::profiles::cloud_name(config, "Mods");
+        if let Some(parent) = cloud_root.join(&mods_name).parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        push_to_cloud_folder(config, local_mods, &mods_filter, cloud_root, &mods_name)?;
+        crate::inventory::upload_inventory(config, local_mods, &mods_filter)?;
+        crate::push_meta::upload_push_meta(config, &mods_name, local_mods, &mods_filter)?;
         messages.push("Mods uploaded to cloud.");
     }
 
@@ -372,3 +829,42 @@ pub fn sync_push(config: &SyncConfig, target: SyncTarget, force: bool) -> Result
         Ok(messages.join(" "))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{pull_from_cloud_folder, sync_lock, SyncConfig};
+    use crate::filter::PathFilter;
+    use std::fs;
+
+    #[test]
+    fn second_sync_is_refused_until_the_first_ends() {
+        let running = sync_lock().unwrap();
+        assert_eq!(sync_lock().unwrap_err(), "A sync is already running");
+        // From another thread too, e.g. the tray's fetch.
+        assert!(std::thread::spawn(|| sync_lock().is_err()).join().unwrap());
+        drop(running);
+        assert!(sync_lock().is_ok());
+    }
+
+    #[test]
+    fn cloud_folder_from_before_markers_is_still_pulled() {
+        let root = std::env::temp_dir().join(format!("syncone_legacy_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let cloud = root.join("cloud");
+        let local = root.join("Save");
+        fs::create_dir_all(cloud.join("Save/SaveGame_1")).unwrap();
+        fs::write(cloud.join("Save/SaveGame_1/Game.json"), "from the cloud").unwrap();
+        fs::create_dir_all(local.join("SaveGame_1")).unwrap();
+        fs::write(local.join("SaveGame_1/Game.json"), "local").unwrap();
+
+        let started = std::time::Instant::now();
+        pull_from_cloud_folder(&SyncConfig::default(), &cloud, "Save", &local, &PathFilter::all()).unwrap();
+        assert!(started.elapsed() < super::CLOUD_SETTLE_POLL);
+        assert_eq!(fs::read_to_string(local.join("SaveGame_1/Game.json")).unwrap(), "from the cloud");
+
+        // Once pushed with markers, a missing marker means a push is under way again.
+        fs::write(cloud.join("Save.manifest.sig"), "{}").unwrap();
+        assert!(!super::is_legacy_cloud_copy(&cloud, "Save"));
+        let _ = fs::remove_dir_all(&root);
+    }
+}
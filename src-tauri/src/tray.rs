@@ -0,0 +1,199 @@
+//! Tray icon: SyncONE keeps running there when the window is closed, checks the sync status in
+//! the background and shows it as a coloured dot on the icon (green in sync, blue local newer,
+//! orange cloud newer, red conflict). The menu fetches or uploads everything without opening the
+//! window; a warning that needs a decision (progress, game running, setup mismatch) opens it.
+
+use crate::sync::{load_config, sync_pull, sync_push, Overrides, SyncState, SyncTarget};
+use serde::Serialize;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::image::Image;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+
+const TRAY_ID: &str = "main";
+/// Background status check interval, and while the cloud is watched (pushes trigger a check).
+const CHECK_SECS: u64 = 60;
+const WATCHED_CHECK_SECS: u64 = 5 * 60;
+
+static CHECK_NOW: OnceLock<Mutex<Sender<()>>> = OnceLock::new();
+static STATUS_ITEM: OnceLock<MenuItem<Wry>> = OnceLock::new();
+
+/// Result of a fetch or upload started from the tray; sent to the UI as `sync-finished`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncFinished {
+    /// "fetch" or "upload".
+    pub action: String,
+    pub ok: bool,
+    pub message: String,
+    /// The error is a warning the user may override; the window was opened to ask.
+    pub warning: bool,
+}
+
+fn describe(state: SyncState) -> &'static str {
+    match state {
+        SyncState::InSync => "in sync",
+        SyncState::LocalNewer => "your changes are not uploaded",
+        SyncState::CloudNewer => "newer version in the cloud",
+        SyncState::Conflict => "save and mods need syncing in opposite directions",
+    }
+}
+
+/// The app icon with a status dot in the bottom-right corner.
+fn badged(base: &Image<'_>, color: [u8; 3]) -> Image<'static> {
+    let (w, h) = (base.width(), base.height());
+    let mut rgba = base.rgba().to_vec();
+    let radius = w.min(h) as f32 * 0.22;
+    let ring = (radius * 0.3).max(1.0);
+    let (cx, cy) = (w as f32 - radius - ring, h as f32 - radius - ring);
+    for y in 0..h {
+        for x in 0..w {
+            let d = ((x as f32 + 0.5 - cx).powi(2) + (y as f32 + 0.5 - cy).powi(2)).sqrt();
+            let pixel = if d <= radius {
+                [color[0], color[1], color[2], 255]
+            } else if d <= radius + ring {
+                [255, 255, 255, 255]
+            } else {
+                continue;
+            };
+            let i = ((y * w + x) * 4) as usize;
+            rgba[i..i + 4].copy_from_slice(&pixel);
+        }
+    }
+    Image::new_owned(rgba, w, h)
+}
+
+fn state_icon(app: &AppHandle, state: SyncState) -> Option<Image<'static>> {
+    let color = match state {
+        SyncState::InSync => [63, 185, 80],
+        SyncState::LocalNewer => [88, 166, 255],
+        SyncState::CloudNewer => [240, 136, 62],
+        SyncState::Conflict => [248, 81, 73],
+    };
+    app.default_window_icon().map(|base| badged(base, color))
+}
+
+fn set_status_text(app: &AppHandle, text: &str) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(format!("SyncONE – {}", text)));
+    }
+    if let Some(item) = STATUS_ITEM.get() {
+        let _ = item.set_text(format!("Status: {}", text));
+    }
+}
+
+fn check_status(app: &AppHandle) {
+    let Ok(config) = load_config() else { return };
+    let state = crate::sync::get_sync_status(&config).state();
+    tracing::debug!(?state, "background status check");
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_icon(state_icon(app, state));
+    }
+    set_status_text(app, describe(state));
+}
+
+/// Re-check the status now instead of at the next interval (after a sync or a cloud change).
+pub fn request_check() {
+    if let Some(tx) = CHECK_NOW.get() {
+        let _ = tx.lock().map(|tx| tx.send(()));
+    }
+}
+
+pub fn show_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Fetch or upload everything in the background, as from the tray menu (also the fetch at login).
+pub(crate) fn run_sync(app: &AppHandle, action: &'static str) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let Ok(config) = load_config() else { return };
+        let _running = match crate::sync::sync_lock() {
+            Ok(guard) => guard,
+            Err(e) => {
+                let finished = SyncFinished { action: action.to_string(), ok: false, message: e, warning: false };
+                let _ = app.emit("sync-finished", finished);
+                return;
+            }
+        };
+        set_status_text(&app, if action == "fetch" { "fetching…" } else { "uploading…" });
+        let result = if action == "fetch" {
+            sync_pull(&config, SyncTarget::Both, Overrides::default())
+        } else {
+            sync_push(&config, SyncTarget::Both, Overrides::default())
+        };
+        crate::activity::record(&config, action, "both", false, &result);
+        crate::notifications::sync_result(&app, action, &result);
+        let warning = result.as_ref().err().is_some_and(|e| crate::activity::forceable_warning(e).is_some());
+        if warning {
+            show_window(&app);
+        }
+        let finished = SyncFinished {
+            action: action.to_string(),
+            ok: result.is_ok(),
+            message: result.unwrap_or_else(|e| e),
+            warning,
+        };
+        let _ = app.emit("sync-finished", finished);
+        request_check();
+    });
+}
+
+/// Create the tray icon and start the background status checks.
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let status = MenuItem::with_id(app, "status", "Status: checking…", true, None::<&str>)?;
+    let fetch = MenuItem::with_id(app, "fetch", "Fetch all from cloud", true, None::<&str>)?;
+    let upload = MenuItem::with_id(app, "upload", "Upload all to cloud", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit SyncONE", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status,
+            &PredefinedMenuItem::separator(app)?,
+            &fetch,
+            &upload,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )?;
+    let _ = STATUS_ITEM.set(status);
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("SyncONE")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            "status" => show_window(app),
+            "fetch" => run_sync(app, "fetch"),
+            "upload" => run_sync(app, "upload"),
+            "quit" => app.exit(0),
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } = event {
+                show_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    let (tx, rx) = mpsc::channel();
+    let _ = CHECK_NOW.set(Mutex::new(tx));
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        check_status(&app);
+        let secs = if crate::cloud_watch::is_active() { WATCHED_CHECK_SECS } else { CHECK_SECS };
+        if let Err(mpsc::RecvTimeoutError::Disconnected) = rx.recv_timeout(Duration::from_secs(secs)) {
+            return;
+        }
+    });
+    Ok(())
+}
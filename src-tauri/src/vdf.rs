@@ -0,0 +1,236 @@
+//! Minimal reader for Valve's KeyValues text format (`.vdf`, `.acf`), enough for Steam's
+//! `libraryfolders.vdf` and `appmanifest_<appid>.acf`:
+//!
+//! ```text
+//! "AppState"
+//! {
+//!     "appid"       "3164500"
+//!     "installdir"  "Schedule I"
+//!     "buildid"     "18211515"
+//! }
+//! ```
+//!
+//! Keys and values are quoted (or bare) strings, a value may be a `{ ... }` block instead, and
+//! `//` starts a comment. `[$WIN32]`-style conditions are skipped. Keys compare ignoring case,
+//! like Steam does.
+
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Vdf {
+    Str(String),
+    Block(Vec<(String, Vdf)>),
+}
+
+impl Vdf {
+    /// The first child with this key (of a block).
+    pub(crate) fn get(&self, key: &str) -> Option<&Vdf> {
+        self.entries().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v)
+    }
+
+    /// The string at a path of keys, e.g. `["AppState", "buildid"]`.
+    pub(crate) fn str_at(&self, path: &[&str]) -> Option<&str> {
+        match path.iter().try_fold(self, |node, key| node.get(key))? {
+            Vdf::Str(s) => Some(s),
+            Vdf::Block(_) => None,
+        }
+    }
+
+    /// Key/value pairs of a block (nothing for a string).
+    pub(crate) fn entries(&self) -> impl Iterator<Item = (&str, &Vdf)> {
+        let entries: &[(String, Vdf)] = match self {
+            Vdf::Block(entries) => entries,
+            Vdf::Str(_) => &[],
+        };
+        entries.iter().map(|(k, v)| (k.as_str(), v))
+    }
+}
+
+enum Token {
+    Str(String),
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '[' => {
+                // Platform condition such as [$WIN32]: applies to the previous pair, ignore it.
+                if !chars.by_ref().any(|c| c == ']') {
+                    return Err("Unterminated [condition] in VDF".to_string());
+                }
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        None => return Err("Unterminated string in VDF".to_string()),
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some(c @ ('\\' | '"')) => s.push(c),
+                            // Unknown escapes (e.g. a lone `\P` in a path) stay as written.
+                            Some(other) => {
+                                s.push('\\');
+                                s.push(other);
+                            }
+                            None => return Err("Unterminated string in VDF".to_string()),
+                        },
+                        Some(other) => s.push(other),
+                    }
+                }
+                tokens.push(Token::Str(s));
+            }
+            c => {
+                let mut s = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || matches!(next, '"' | '{' | '}') {
+                        break;
+                    }
+                    s.push(next);
+                    chars.next();
+                }
+                tokens.push(Token::Str(s));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_block(tokens: &mut std::vec::IntoIter<Token>, nested: bool) -> Result<Vdf, String> {
+    let mut entries = Vec::new();
+    loop {
+        let key = match tokens.next() {
+            Some(Token::Str(key)) => key,
+            Some(Token::Close) if nested => return Ok(Vdf::Block(entries)),
+            None if !nested => return Ok(Vdf::Block(entries)),
+            None => return Err("Missing } in VDF".to_string()),
+            Some(_) => return Err("Expected a key in VDF".to_string()),
+        };
+        let value = match tokens.next() {
+            Some(Token::Str(value)) => Vdf::Str(value),
+            Some(Token::Open) => parse_block(tokens, true)?,
+            _ => return Err(format!("Missing value for \"{}\" in VDF", key)),
+        };
+        entries.push((key, value));
+    }
+}
+
+/// Parse a whole document; the result is a block holding its top-level pairs.
+pub(crate) fn parse(text: &str) -> Result<Vdf, String> {
+    parse_block(&mut tokenize(text)?.into_iter(), false)
+}
+
+pub(crate) fn read(path: &Path) -> Result<Vdf, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Vdf};
+
+    #[test]
+    fn reads_new_style_library_folders() {
+        let vdf = parse(
+            r#"
+"libraryfolders"
+{
+	"0"
+	{
+		"path"		"C:\\Program Files (x86)\\Steam"
+		"label"		""
+		"apps"
+		{
+			"228980"		"304384418"
+		}
+	}
+	"1"
+	{
+		"path"		"D:\\SteamLibrary"
+		"apps"
+		{
+			"3164500"		"1822179584"
+		}
+	}
+}
+"#,
+        )
+        .unwrap();
+        let folders = vdf.get("LibraryFolders").unwrap();
+        let paths: Vec<_> = folders.entries().filter_map(|(_, f)| f.str_at(&["path"])).collect();
+        assert_eq!(paths, [r"C:\Program Files (x86)\Steam", r"D:\SteamLibrary"]);
+        assert_eq!(folders.str_at(&["1", "apps", "3164500"]), Some("1822179584"));
+    }
+
+    #[test]
+    fn reads_old_style_library_folders() {
+        let vdf = parse(
+            r#"
+"LibraryFolders"
+{
+	"TimeNextStatsReport"		"1700000000"
+	"ContentStatsID"		"-4321"
+	"1"		"D:\\SteamLibrary"
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(vdf.str_at(&["libraryfolders", "1"]), Some(r"D:\SteamLibrary"));
+        assert_eq!(vdf.str_at(&["libraryfolders", "TimeNextStatsReport"]), Some("1700000000"));
+    }
+
+    #[test]
+    fn reads_app_manifest_with_comments_and_conditions() {
+        let vdf = parse(
+            r#"
+// Written by Steam
+"AppState"
+{
+	"appid"		"3164500"
+	"installdir"		"Schedule I" // the folder under steamapps/common
+	"buildid"		"18211515"
+	"LauncherPath"		"C:\\Program Files (x86)\\Steam\\steam.exe"	[$WIN32]
+	"UserConfig" { language english }
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(vdf.str_at(&["AppState", "installdir"]), Some("Schedule I"));
+        assert_eq!(vdf.str_at(&["appstate", "BuildID"]), Some("18211515"));
+        assert_eq!(vdf.str_at(&["AppState", "UserConfig", "language"]), Some("english"));
+        assert_eq!(vdf.str_at(&["AppState", "UserConfig"]), None);
+        assert_eq!(vdf.get("AppState").unwrap().entries().count(), 5);
+    }
+
+    #[test]
+    fn keeps_unknown_escapes() {
+        let vdf = parse(r#""path" "C:\Games\Steam\n\P\"x\"" "tab" "a\tb""#).unwrap();
+        assert_eq!(vdf.str_at(&["path"]), Some("C:\\Games\\Steam\n\\P\"x\""));
+        assert_eq!(vdf.get("tab"), Some(&Vdf::Str("a\tb".to_string())));
+    }
+
+    #[test]
+    fn rejects_unterminated_input() {
+        assert_eq!(parse(r#""AppState" { "appid" "3164500"#).unwrap_err(), "Unterminated string in VDF");
+        assert_eq!(parse(r#""AppState" { "appid" "3164500""#).unwrap_err(), "Missing } in VDF");
+        assert_eq!(parse(r#""appid" "3164500" [$WIN32"#).unwrap_err(), "Unterminated [condition] in VDF");
+        assert_eq!(parse(r#""AppState""#).unwrap_err(), "Missing value for \"AppState\" in VDF");
+        assert_eq!(parse("}").unwrap_err(), "Expected a key in VDF");
+    }
+}
@@ -1,16 +1,50 @@
 import { getVersion } from "@tauri-apps/api/app";
 import { invoke } from "@tauri-apps/api/core";
-import { open } from "@tauri-apps/plugin-dialog";
+import { listen } from "@tauri-apps/api/event";
+import { open, save } from "@tauri-apps/plugin-dialog";
 import { check } from "@tauri-apps/plugin-updater";
 import { relaunch } from "@tauri-apps/plugin-process";
 
 interface SyncConfig {
   save_path: string | null;
   mods_path: string | null;
+  game_root: string | null;
+  game_exe: string | null;
+  mod_folders: string[];
+  mod_includes: string[];
+  mod_excludes: string[];
+  save_includes: string[];
+  save_excludes: string[];
+  save_local_only: string[];
+  mod_local_only: string[];
   cloud_path: string | null;
   supabase_url: string | null;
   supabase_key: string | null;
   bucket_name: string | null;
+  cloud_zip: boolean;
+  encrypt_archives: boolean;
+  trusted_signers: { name: string; public_key: string }[];
+  allow_unsigned_mods: boolean;
+  blob_store: boolean;
+  git_remote: string | null;
+  git_branch: string | null;
+  mod_profile: string | null;
+  display_name: string | null;
+  quit_on_close: boolean;
+  run_at_startup: boolean;
+  notifications: {
+    sync_results: boolean;
+    warnings: boolean;
+    handoffs: boolean;
+    cloud_changes: boolean;
+  };
+}
+
+interface ModProfile {
+  name: string;
+  active: boolean;
+  cached: boolean;
+  in_cloud: boolean;
 }
 
 interface SyncStatus {
@@ -24,6 +58,56 @@ interface SyncStatus {
   mods_cloud_newer: boolean;
   save_path_used: string | null;
   mods_path_used: string | null;
+  save_last_push: PushMeta | null;
+  mods_last_push: PushMeta | null;
+}
+
+interface SaveVersion {
+  commit: string;
+  timestamp: number;
+  message: string;
+}
+
+interface BlobVersion {
+  id: string;
+  timestamp: number;
+  file_count: number;
+  total_size: number;
+}
+
+interface PushMeta {
+  pushed_at: number;
+  pushed_by: string | null;
+  hostname: string | null;
+  app_version: string | null;
+  note: string | null;
+  lifetime_earnings: number | null;
+  file_count: number | null;
+}
+
+function formatAgo(ts: number): string {
+  const seconds = Math.max(0, Date.now() / 1000 - ts);
+  if (seconds < 60) return "just now";
+  if (seconds < 3600) return `${Math.floor(seconds / 60)}m ago`;
+  if (seconds < 86400) return `${Math.floor(seconds / 3600)}h ago`;
+  return `${Math.floor(seconds / 86400)}d ago`;
+}
+
+/** "Last pushed by Anna 2h ago: 'finished the warehouse'", details in the tooltip. */
+function renderLastPush(el: HTMLElement, push: PushMeta | null) {
+  if (!push) {
+    el.textContent = "";
+    el.title = "";
+    return;
+  }
+  const note = push.note ? `: '${push.note}'` : "";
+  el.textContent = `Last pushed by ${push.pushed_by ?? "someone"} ${formatAgo(push.pushed_at)}${note}`;
+  const details = [`Pushed ${formatMtime(push.pushed_at)}`];
+  if (push.hostname) details.push(`PC: ${push.hostname}`);
+  if (push.app_version) details.push(`SyncONE v${push.app_version}`);
+  if (push.lifetime_earnings != null) details.push(`Lifetime earnings: ${push.lifetime_earnings.toFixed(0)}`);
+  if (push.file_count != null) details.push(`${push.file_count} files`);
+  el.title = details.join("\n");
 }
 
 function formatMtime(ts: number | null): string {
@@ -39,20 +123,49 @@ function formatMtime(ts: number | null): string {
 
 const savePathEl = document.querySelector("#save-path") as HTMLInputElement;
 const modsPathEl = document.querySelector("#mods-path") as HTMLInputElement;
+const gameRootEl = document.querySelector("#game-root") as HTMLInputElement;
+const gameExeEl = document.querySelector("#game-exe") as HTMLInputElement;
+const displayNameEl = document.querySelector("#display-name") as HTMLInputElement;
+const pushNoteEl = document.querySelector("#push-note") as HTMLInputElement;
+const modFoldersEl = document.querySelector("#mod-folders") as HTMLInputElement;
+const modIncludesEl = document.querySelector("#mod-includes") as HTMLTextAreaElement;
+const modExcludesEl = document.querySelector("#mod-excludes") as HTMLTextAreaElement;
+const saveIncludesEl = document.querySelector("#save-includes") as HTMLTextAreaElement;
+const saveExcludesEl = document.querySelector("#save-excludes") as HTMLTextAreaElement;
+const saveLocalOnlyEl = document.querySelector("#save-local-only") as HTMLTextAreaElement;
+const modLocalOnlyEl = document.querySelector("#mod-local-only") as HTMLTextAreaElement;
 const cloudPathEl = document.querySelector("#cloud-path") as HTMLInputElement;
 const supabaseUrlEl = document.querySelector("#supabase-url") as HTMLInputElement;
 const supabaseKeyEl = document.querySelector("#supabase-key") as HTMLInputElement;
 const bucketNameEl = document.querySelector("#bucket-name") as HTMLInputElement;
+const cloudZipEl = document.querySelector("#cloud-zip") as HTMLInputElement;
+const blobStoreEl = document.querySelector("#blob-store") as HTMLInputElement;
+const encryptArchivesEl = document.querySelector("#encrypt-archives") as HTMLInputElement;
+const groupPassphraseEl = document.querySelector("#group-passphrase") as HTMLInputElement;
+const identityKeyEl = document.querySelector("#identity-key") as HTMLInputElement;
+const trustedSignersEl = document.querySelector("#trusted-signers") as HTMLTextAreaElement;
+const allowUnsignedModsEl = document.querySelector("#allow-unsigned-mods") as HTMLInputElement;
+const quitOnCloseEl = document.querySelector("#quit-on-close") as HTMLInputElement;
+const notifySyncResultsEl = document.querySelector("#notify-sync-results") as HTMLInputElement;
+const notifyWarningsEl = document.querySelector("#notify-warnings") as HTMLInputElement;
+const notifyHandoffsEl = document.querySelector("#notify-handoffs") as HTMLInputElement;
+const notifyCloudChangesEl = document.querySelector("#notify-cloud-changes") as HTMLInputElement;
+const gitRemoteEl = document.querySelector("#git-remote") as HTMLInputElement;
 const saveConfigBtn = document.querySelector("#save-config");
 const browseSaveBtn = document.querySelector("#browse-save");
 const browseModsBtn = document.querySelector("#browse-mods");
 const browseCloudBtn = document.querySelector("#browse-cloud");
+const browseGameRootBtn = document.querySelector("#browse-game-root");
+const detectPathsBtn = document.querySelector("#detect-paths");
+const detectedPathsEl = document.querySelector("#detected-paths") as HTMLElement;
 const syncPullBtn = document.querySelector("#sync-pull");
 const syncPushBtn = document.querySelector("#sync-push");
 const syncStatusEl = document.querySelector("#sync-status") as HTMLElement;
 const runAtStartupEl = document.querySelector("#run-at-startup") as HTMLInputElement;
-const openStartupBtn = document.querySelector("#open-startup");
 const refreshStatusBtn = document.querySelector("#refresh-status");
+const modProfileEl = document.querySelector("#mod-profile") as HTMLInputElement;
+const modProfileListEl = document.querySelector("#mod-profile-list") as HTMLDataListElement;
+const switchModProfileBtn = document.querySelector("#switch-mod-profile");
 const saveLocalTimeEl = document.querySelector("#save-local-time") as HTMLElement;
 const saveCloudTimeEl = document.querySelector("#save-cloud-time") as HTMLElement;
 const modsLocalTimeEl = document.querySelector("#mods-local-time") as HTMLElement;
@@ -61,6 +174,24 @@ const saveBadgeEl = document.querySelector("#save-badge") as HTMLElement;
 const modsBadgeEl = document.querySelector("#mods-badge") as HTMLElement;
 const savePathUsedEl = document.querySelector("#save-path-used") as HTMLElement;
 const modsPathUsedEl = document.querySelector("#mods-path-used") as HTMLElement;
+const saveLastPushEl = document.querySelector("#save-last-push") as HTMLElement;
+const activityEl = document.querySelector("#activity") as HTMLDetailsElement;
+const activityTargetEl = document.querySelector("#activity-target") as HTMLSelectElement;
+const activityUserEl = document.querySelector("#activity-user") as HTMLInputElement;
+const activityOutcomeEl = document.querySelector("#activity-outcome") as HTMLSelectElement;
+const activityListEl = document.querySelector("#activity-list") as HTMLElement;
+const refreshActivityBtn = document.querySelector("#refresh-activity");
+const versionsEl = document.querySelector("#versions") as HTMLDetailsElement;
+const versionsListEl = document.querySelector("#versions-list") as HTMLElement;
+const versionsCleanupEl = document.querySelector("#versions-cleanup") as HTMLElement;
+const versionsKeepEl = document.querySelector("#versions-keep") as HTMLInputElement;
+const collectGarbageBtn = document.querySelector("#collect-garbage");
+const modsLastPushEl = document.querySelector("#mods-last-push") as HTMLElement;
+const exportDiagnosticsBtn = document.querySelector("#export-diagnostics");
+const presenceEl = document.querySelector("#presence") as HTMLElement;
+const handoffToEl = document.querySelector("#handoff-to") as HTMLInputElement;
+const handoffMembersEl = document.querySelector("#handoff-members") as HTMLElement;
+const handoffBtn = document.querySelector("#handoff");
 
 function setStatus(text: string, isError = false) {
   if (!syncStatusEl) return;
@@ -68,22 +199,80 @@ function setStatus(text: string, isError = false) {
   syncStatusEl.className = "status " + (isError ? "error" : "");
 }
 
+// Last config loaded from the backend; fields without an input here are passed through unchanged on save.
+let loadedConfig: Partial<SyncConfig> = {};
+
 async function loadConfig() {
   try {
     const config = await invoke<SyncConfig>("get_config");
+    loadedConfig = config;
     savePathEl.value = config.save_path ?? "";
     modsPathEl.value = config.mods_path ?? "";
     cloudPathEl.value = config.cloud_path ?? "";
     supabaseUrlEl.value = config.supabase_url ?? "";
     supabaseKeyEl.value = config.supabase_key ?? "";
     bucketNameEl.value = config.bucket_name ?? "";
-    const stored = localStorage.getItem("syncone_run_at_startup");
-    runAtStartupEl.checked = stored === "true";
+    cloudZipEl.checked = config.cloud_zip ?? false;
+    blobStoreEl.checked = config.blob_store ?? false;
+    gameRootEl.value = config.game_root ?? "";
+    gameExeEl.value = config.game_exe ?? "Schedule I.exe";
+    displayNameEl.value = config.display_name ?? "";
+    modFoldersEl.value = (config.mod_folders ?? []).join(", ");
+    modIncludesEl.value = (config.mod_includes ?? []).join("\n");
+    modExcludesEl.value = (config.mod_excludes ?? []).join("\n");
+    saveIncludesEl.value = (config.save_includes ?? []).join("\n");
+    saveExcludesEl.value = (config.save_excludes ?? []).join("\n");
+    saveLocalOnlyEl.value = (config.save_local_only ?? []).join("\n");
+    modLocalOnlyEl.value = (config.mod_local_only ?? []).join("\n");
+    gitRemoteEl.value = config.git_remote ?? "";
+    encryptArchivesEl.checked = config.encrypt_archives ?? false;
+    groupPassphraseEl.value = "";
+    groupPassphraseEl.placeholder = (await invoke<boolean>("has_group_passphrase"))
+      ? "Passphrase saved on this PC – type to change"
+      : "Shared with your group";
+    trustedSignersEl.value = (config.trusted_signers ?? [])
+      .map((s) => `${s.name}: ${s.public_key}`)
+      .join("\n");
+    allowUnsignedModsEl.checked = config.allow_unsigned_mods ?? false;
+    quitOnCloseEl.checked = config.quit_on_close ?? false;
+    notifySyncResultsEl.checked = config.notifications?.sync_results ?? true;
+    notifyWarningsEl.checked = config.notifications?.warnings ?? true;
+    notifyHandoffsEl.checked = config.notifications?.handoffs ?? true;
+    notifyCloudChangesEl.checked = config.notifications?.cloud_changes ?? true;
+    identityKeyEl.value = await invoke<string>("get_identity_public_key").catch(() => "");
+    runAtStartupEl.checked = config.run_at_startup ?? false;
   } catch (e) {
     setStatus("Could not load settings: " + String(e), true);
   }
 }
 
+// Older versions only remembered the startup checkbox here; the setting now lives in the config.
+const LEGACY_STARTUP_KEY = "syncone_run_at_startup";
+
+/** Carry over the old checkbox once, then warn about shortcuts left from the manual setup. */
+async function migrateLegacyStartup() {
+  if (localStorage.getItem(LEGACY_STARTUP_KEY) === "true" && !runAtStartupEl.checked) {
+    runAtStartupEl.checked = true;
+    // Warns about old shortcuts itself.
+    if (await setRunAtStartup()) localStorage.removeItem(LEGACY_STARTUP_KEY);
+    return;
+  }
+  localStorage.removeItem(LEGACY_STARTUP_KEY);
+  await warnLegacyStartup();
+}
+
+/** Warn about hand-made startup shortcuts, which would start SyncONE twice at login. */
+async function warnLegacyStartup(): Promise<boolean> {
+  if (!runAtStartupEl.checked) return false;
+  const entries = await invoke<string[]>("get_legacy_startup_entries").catch(() => []);
+  if (entries.length === 0) return false;
+  setStatus(
+    `SyncONE now starts itself at login, so it would start twice: delete ${entries.join(", ")} from your Startup folder.`,
+    true
+  );
+  return true;
+}
+
 async function pickDirectory(currentPath: string): Promise<string | null> {
   const selected = await open({
     directory: true,
@@ -102,17 +291,70 @@ function bindBrowse(button: Element | null, input: HTMLInputElement) {
   });
 }
 
+// One signer per line: "Name: <public key>"
+function parseTrustedSigners(text: string): { name: string; public_key: string }[] {
+  return text
+    .split("\n")
+    .map((line) => line.trim())
+    .filter((line) => line.includes(":"))
+    .map((line) => {
+      const idx = line.lastIndexOf(":");
+      return { name: line.slice(0, idx).trim(), public_key: line.slice(idx + 1).trim() };
+    })
+    .filter((s) => s.public_key.length > 0);
+}
+
+// One glob pattern or path per line
+function parsePatterns(text: string): string[] {
+  return text
+    .split("\n")
+    .map((p) => p.trim())
+    .filter(Boolean);
+}
+
 async function saveConfig() {
   const config: SyncConfig = {
+    ...(loadedConfig as SyncConfig),
     save_path: savePathEl.value.trim() || null,
     mods_path: modsPathEl.value.trim() || null,
+    game_root: gameRootEl.value.trim() || null,
+    // Empty turns the "game is running" check off.
+    game_exe: gameExeEl.value.trim(),
+    display_name: displayNameEl.value.trim() || null,
+    mod_folders: modFoldersEl.value.split(",").map((f) => f.trim()).filter(Boolean),
+    mod_includes: parsePatterns(modIncludesEl.value),
+    // Empty folder and exclude boxes mean "use the defaults" (logs, temp files, caches, …).
+    mod_excludes: parsePatterns(modExcludesEl.value),
+    save_includes: parsePatterns(saveIncludesEl.value),
+    save_excludes: parsePatterns(saveExcludesEl.value),
+    save_local_only: parsePatterns(saveLocalOnlyEl.value),
+    mod_local_only: parsePatterns(modLocalOnlyEl.value),
     cloud_path: cloudPathEl.value.trim() || null,
     supabase_url: supabaseUrlEl.value.trim() || null,
     supabase_key: supabaseKeyEl.value.trim() || null,
     bucket_name: bucketNameEl.value.trim() || null,
+    cloud_zip: cloudZipEl.checked,
+    blob_store: blobStoreEl.checked,
+    encrypt_archives: encryptArchivesEl.checked,
+    trusted_signers: parseTrustedSigners(trustedSignersEl.value),
+    allow_unsigned_mods: allowUnsignedModsEl.checked,
+    quit_on_close: quitOnCloseEl.checked,
+    notifications: {
+      sync_results: notifySyncResultsEl.checked,
+      warnings: notifyWarningsEl.checked,
+      handoffs: notifyHandoffsEl.checked,
+      cloud_changes: notifyCloudChangesEl.checked,
+    },
+    git_remote: gitRemoteEl.value.trim() || null,
   };
   try {
+    const passphrase = groupPassphraseEl.value;
+    if (passphrase) {
+      await invoke("set_group_passphrase", { passphrase });
+      groupPassphraseEl.value = "";
+    }
     await invoke("set_config", { config });
+    loadedConfig = config;
     setStatus("Paths saved.");
     await refreshSyncStatus();
   } catch (e) {
@@ -155,6 +397,8 @@ async function refreshSyncStatus() {
     }
     renderBadge(saveBadgeEl, s.save_local_newer, s.save_cloud_newer);
     renderBadge(modsBadgeEl, s.mods_local_newer, s.mods_cloud_newer);
+    renderLastPush(saveLastPushEl, s.save_last_push);
+    renderLastPush(modsLastPushEl, s.mods_last_push);
   } catch {
     saveLocalTimeEl.textContent = "–";
     saveCloudTimeEl.textContent = "–";
@@ -166,11 +410,29 @@ async function refreshSyncStatus() {
     saveBadgeEl.className = "status-badge";
     modsBadgeEl.textContent = "";
     modsBadgeEl.className = "status-badge";
+    renderLastPush(saveLastPushEl, null);
+    renderLastPush(modsLastPushEl, null);
   }
 }
 
 type SyncTarget = "save" | "mods" | "both";
 
+interface ModEntry {
+  path: string;
+  name: string;
+  version: string | null;
+  size: number;
+  sha256: string;
+}
+
+interface ModsInventoryDiff {
+  cloud_created_at: number | null;
+  added: ModEntry[];
+  removed: ModEntry[];
+  updated: { path: string; name: string; local_version: string | null; cloud_version: string | null }[];
+  unchanged: number;
+}
+
 const modalOverlay = document.getElementById("modal-overlay") as HTMLElement;
 const modalBody = document.getElementById("modal-body") as HTMLElement;
 const modalCancel = document.getElementById("modal-cancel") as HTMLElement;
@@ -195,23 +457,475 @@ function showModal(body: string, confirmLabel = "Upload anyway"): Promise<boolea
   });
 }
 
-async function doSyncPull(target: SyncTarget = "both", force = false, successMessage?: string) {
+function describeModChanges(diff: ModsInventoryDiff): string {
+  const withVersion = (m: ModEntry) => (m.version ? `${m.name} ${m.version}` : m.path);
+  const lines = ["Fetching mods will change your mods folder:", ""];
+  for (const m of diff.added) lines.push(`+ ${withVersion(m)}`);
+  for (const m of diff.updated) {
+    lines.push(`~ ${m.name} ${m.local_version ?? "?"} → ${m.cloud_version ?? "?"}`);
+  }
+  for (const m of diff.removed) lines.push(`- ${withVersion(m)}`);
+  return lines.join("\n");
+}
+
+/** Show what a mods fetch would add/remove/update and ask before going ahead. */
+async function confirmModChanges(): Promise<boolean> {
+  let diff: ModsInventoryDiff;
+  try {
+    diff = await invoke<ModsInventoryDiff>("get_mods_inventory");
+  } catch {
+    return true; // No inventory (e.g. uploaded by an older version): fetch as before.
+  }
+  if (diff.cloud_created_at == null) return true;
+  if (diff.added.length + diff.removed.length + diff.updated.length === 0) return true;
+  return showModal(describeModChanges(diff), "Fetch mods");
+}
+
+interface SaveSlot {
+  folder: string;
+  name: string | null;
+  last_played: number | null;
+}
+
+interface DiscoveredPaths {
+  saves: { path: string; steam_id: string; source: string; slots: SaveSlot[]; last_played: number | null }[];
+  games: { path: string; library: string; build_id: string | null }[];
+}
+
+/** List save folders and game installs found on this PC; clicking one fills in the setting. */
+async function detectPaths() {
+  const found = await invoke<DiscoveredPaths>("discover_paths");
+  const pick = (label: string, title: string, input: HTMLInputElement, value: string) => {
+    const btn = document.createElement("button");
+    btn.type = "button";
+    btn.textContent = label;
+    btn.title = title;
+    btn.addEventListener("click", () => {
+      input.value = value;
+      setStatus("Filled in from detected folders. Save settings to keep it.");
+    });
+    return btn;
+  };
+  const buttons = [
+    ...found.saves.map((s) => {
+      const slots = s.slots.map((slot) => slot.name ?? slot.folder).join(", ") || "no save slots";
+      return pick(
+        `Save ${s.steam_id} (${s.source}) – ${slots} – last played ${formatMtime(s.last_played)}`,
+        s.path,
+        savePathEl,
+        s.path
+      );
+    }),
+    ...found.games.map((g) => {
+      const build = g.build_id ? ` (build ${g.build_id})` : "";
+      return pick(`Game folder – ${g.path}${build}`, g.library, gameRootEl, g.path);
+    }),
+  ];
+  detectedPathsEl.replaceChildren(...buttons);
+  detectedPathsEl.classList.toggle("hidden", buttons.length === 0);
+  if (buttons.length === 0) setStatus("No Schedule I save or game folder found. Use Browse instead.", true);
+}
+
+interface ActivityEntry {
+  at: number;
+  user: string;
+  hostname: string | null;
+  action: string;
+  target: string;
+  outcome: string;
+  message: string;
+}
+
+interface PresenceMember {
+  user: string;
+  hostname: string | null;
+  profile: string | null;
+  game_running: boolean;
+  hosting: boolean;
+  online_since: number;
+  heartbeat_at: number;
+  this_pc: boolean;
+}
+
+async function loadPresence() {
+  try {
+    const presence = await invoke<{ members: PresenceMember[]; host: string | null; error: string | null }>("get_presence");
+    presenceEl.title = "";
+    if (presence.error) {
+      presenceEl.textContent = "";
+      presenceEl.title = presence.error;
+      return;
+    }
+    const others = presence.members.filter((m) => !m.this_pc);
+    handoffMembersEl.replaceChildren(
+      ...others.map((m) => {
+        const option = document.createElement("option");
+        option.value = m.user;
+        return option;
+      })
+    );
+    if (others.length === 0) {
+      presenceEl.textContent = "Nobody else is online.";
+      return;
+    }
+    const parts = others.map((m) => {
+      const span = document.createElement("span");
+      const state = m.hosting ? "hosting" : m.game_running ? "in game" : "online";
+      const profile = m.profile ? `, mods: ${m.profile}` : "";
+      span.textContent = `${m.user} (${state}${profile})`;
+      span.title = `${m.hostname ?? "unknown PC"} – since ${formatMtime(m.online_since)}`;
+      if (m.hosting) span.className = "hosting";
+      return span;
+    });
+    presenceEl.replaceChildren(document.createTextNode("Online: "));
+    parts.forEach((span, i) => {
+      if (i > 0) presenceEl.append(", ");
+      presenceEl.append(span);
+    });
+  } catch (e) {
+    presenceEl.textContent = "";
+    presenceEl.title = String(e);
+  }
+}
+
+interface HostLease {
+  holder: string | null;
+  next_host: string | null;
+  handoff_by: string | null;
+  handoff_at: number | null;
+  note: string | null;
+}
+
+async function doHandoff(overrides: Overrides = {}) {
+  const nextHost = handoffToEl.value.trim();
+  if (!nextHost) {
+    setStatus("Enter who hosts next.", true);
+    return;
+  }
+  setStatus(`Uploading save and handing off to ${nextHost}...`);
+  try {
+    const result = await invoke<{ ok: boolean; message: string }>("handoff", {
+      nextHost,
+      overrides,
+      note: pushNoteEl.value.trim() || undefined,
+    });
+    pushNoteEl.value = "";
+    handoffToEl.value = "";
+    setStatus(result.message);
+    await refreshSyncStatus();
+  } catch (e) {
+    const msg = String(e);
+    const warning = forceableWarning(msg);
+    if (warning != null) {
+      setStatus("");
+      if (await showModal(warning.text)) {
+        await doHandoff({ ...overrides, [warning.override]: true });
+      } else {
+        setStatus("Handoff cancelled.");
+      }
+    } else {
+      setStatus("Error: " + msg, true);
+    }
+  }
+}
+
+async function acceptHandoff(overrides: Overrides = {}) {
+  setStatus("Fetching the save to take over as host...");
+  try {
+    const result = await invoke<{ ok: boolean; message: string }>("accept_handoff", { overrides });
+    setStatus(result.message);
+    await refreshSyncStatus();
+  } catch (e) {
+    const msg = String(e);
+    const warning = forceableWarning(msg);
+    if (warning != null) {
+      setStatus("");
+      if (await showModal(warning.text, "Fetch anyway")) {
+        await acceptHandoff({ ...overrides, [warning.override]: true });
+      } else {
+        setStatus("Handoff not accepted.");
+      }
+    } else {
+      setStatus("Error: " + msg, true);
+    }
+  }
+}
+
+// Handoff already asked about, so polling and the event don't ask twice.
+let offeredHandoffAt: number | null = null;
+
+async function offerHandoff(lease: HostLease) {
+  if (lease.handoff_at == null || lease.handoff_at === offeredHandoffAt) return;
+  offeredHandoffAt = lease.handoff_at;
+  const note = lease.note ? `\n\n"${lease.note}"` : "";
+  const body = `${lease.handoff_by ?? "Someone"} handed hosting over to you.${note}\n\nFetch the save and take over as host?`;
+  if (await showModal(body, "Take over")) {
+    await acceptHandoff();
+  } else {
+    setStatus("Handoff not accepted yet – it stays open until you take over.");
+  }
+}
+
+// Fallback for when the cloud isn't watched: look for a handoff on every poll.
+async function checkHandoff() {
+  try {
+    const lease = await invoke<HostLease | null>("get_handoff_offer");
+    if (lease) await offerHandoff(lease);
+  } catch {
+    // git backend or no cloud configured: no handoffs
+  }
+}
+
+const ACTION_LABELS: Record<string, string> = {
+  fetch: "fetched",
+  upload: "uploaded",
+  restore: "restored",
+  switch_profile: "switched mod profile",
+  handoff: "handed off hosting",
+  accept_handoff: "took over hosting",
+};
+// Actions whose label already says what they affected.
+const UNTARGETED_ACTIONS = ["switch_profile", "handoff", "accept_handoff"];
+
+async function loadActivity() {
+  try {
+    const feed = await invoke<{ entries: ActivityEntry[]; cloud_error: string | null }>("get_activity", {
+      target: activityTargetEl.value || undefined,
+      user: activityUserEl.value.trim() || undefined,
+      outcome: activityOutcomeEl.value || undefined,
+    });
+    const items = feed.entries.map((e) => {
+      const li = document.createElement("li");
+      li.className = `outcome-${e.outcome}`;
+      const what = UNTARGETED_ACTIONS.includes(e.action) ? ACTION_LABELS[e.action] : `${ACTION_LABELS[e.action] ?? e.action} ${e.target}`;
+      const outcome = e.outcome === "ok" ? "" : ` (${e.outcome})`;
+      li.textContent = `${formatMtime(e.at)} – ${e.user} ${what}${outcome}: ${e.message}`;
+      li.title = e.hostname ? `PC: ${e.hostname}` : "";
+      return li;
+    });
+    if (feed.cloud_error) {
+      const li = document.createElement("li");
+      li.className = "outcome-failed";
+      li.textContent = `Shared activity is incomplete – ${feed.cloud_error}`;
+      items.unshift(li);
+    }
+    if (items.length === 0) {
+      const li = document.createElement("li");
+      li.textContent = "No activity yet.";
+      items.push(li);
+    }
+    activityListEl.replaceChildren(...items);
+  } catch (e) {
+    setStatus("Could not load activity: " + String(e), true);
+  }
+}
+
+function versionItem(text: string, onRestore: () => Promise<void>): HTMLLIElement {
+  const li = document.createElement("li");
+  li.textContent = text + " ";
+  const btn = document.createElement("button");
+  btn.type = "button";
+  btn.className = "link-btn";
+  btn.textContent = "Restore";
+  btn.addEventListener("click", () => void onRestore());
+  li.append(btn);
+  return li;
+}
+
+function messageItem(text: string, className = ""): HTMLLIElement {
+  const li = document.createElement("li");
+  li.className = className;
+  li.textContent = text;
+  return li;
+}
+
+/** Earlier versions kept by the git backend (save) or the blob store (save and mods), each with a restore button. */
+async function loadVersions() {
+  const useGit = !!loadedConfig.git_remote?.trim();
+  versionsCleanupEl.classList.toggle("hidden", useGit || !loadedConfig.blob_store);
+  if (!useGit && !loadedConfig.blob_store) {
+    versionsListEl.replaceChildren(messageItem("Version history needs the git repository or file-by-file sync."));
+    return;
+  }
+  try {
+    let items: HTMLLIElement[];
+    if (useGit) {
+      const versions = await invoke<SaveVersion[]>("get_save_history");
+      items = versions.map((v) =>
+        versionItem(`${formatMtime(v.timestamp)} – ${v.message}`, () => restoreSaveVersion(v))
+      );
+    } else {
+      items = [];
+      for (const name of ["Save", "Mods"]) {
+        const versions = await invoke<BlobVersion[]>("get_blob_history", { name });
+        items.push(
+          ...versions.map((v) =>
+            versionItem(
+              `${name} ${formatMtime(v.timestamp)} – ${v.file_count} files, ${(v.total_size / 1024 / 1024).toFixed(1)} MB`,
+              () => restoreBlobVersion(name, v)
+            )
+          )
+        );
+      }
+    }
+    versionsListEl.replaceChildren(...(items.length ? items : [messageItem("No uploads yet.")]));
+  } catch (e) {
+    versionsListEl.replaceChildren(messageItem("Could not load versions: " + String(e), "outcome-failed"));
+  }
+}
+
+async function restoreBlobVersion(name: string, version: BlobVersion, overrides?: Overrides) {
+  const when = formatMtime(version.timestamp);
+  const what = name === "Save" ? "save" : "mods";
+  if (!overrides && !(await showModal(`Replace the ${what} on this PC with the version from ${when}?`, "Restore"))) return;
+  try {
+    const result = await invoke<{ ok: boolean; message: string }>("restore_blob_version", {
+      name,
+      id: version.id,
+      overrides,
+    });
+    setStatus(result.message);
+    await refreshSyncStatus();
+  } catch (e) {
+    const msg = String(e);
+    const warning = forceableWarning(msg);
+    if (warning != null && (await showModal(warning.text, "Restore anyway"))) {
+      await restoreBlobVersion(name, version, { ...overrides, [warning.override]: true });
+    } else {
+      setStatus(warning != null ? "Restore cancelled." : "Error: " + msg, warning == null);
+    }
+  }
+}
+
+/** Delete all but the newest versions and the files no version needs any more. */
+async function collectGarbage() {
+  const keep = Math.max(1, Math.floor(Number(versionsKeepEl.value) || 1));
+  const body = `Delete all but the newest ${keep} versions of the save and mods for everyone, and the files only they used? This can't be undone.`;
+  if (!(await showModal(body, "Clean up"))) return;
+  try {
+    const report = await invoke<{ versions_pruned: number; blobs_deleted: number; blobs_kept: number }>(
+      "collect_blob_garbage",
+      { keepVersions: keep }
+    );
+    setStatus(`Removed ${report.versions_pruned} old versions and ${report.blobs_deleted} unused files (${report.blobs_kept} kept).`);
+    await loadVersions();
+  } catch (e) {
+    setStatus("Could not clean up: " + String(e), true);
+  }
+}
+
+async function restoreSaveVersion(version: SaveVersion, overrides?: Overrides) {
+  const when = formatMtime(version.timestamp);
+  if (!overrides && !(await showModal(`Replace the save on this PC with the version from ${when}?`, "Restore"))) return;
+  try {
+    const result = await invoke<{ ok: boolean; message: string }>("restore_save_version", {
+      commit: version.commit,
+      overrides,
+    });
+    setStatus(result.message);
+    await refreshSyncStatus();
+  } catch (e) {
+    const msg = String(e);
+    const warning = forceableWarning(msg);
+    if (warning != null && (await showModal(warning.text, "Restore anyway"))) {
+      await restoreSaveVersion(version, { ...overrides, [warning.override]: true });
+    } else {
+      setStatus(warning != null ? "Restore cancelled." : "Error: " + msg, warning == null);
+    }
+  }
+}
+
+async function exportDiagnostics() {
+  const date = new Date().toISOString().slice(0, 10);
+  const path = await save({
+    defaultPath: `syncone-diagnostics-${date}.zip`,
+    filters: [{ name: "Zip", extensions: ["zip"] }],
+  });
+  if (!path) return;
+  try {
+    const result = await invoke<{ ok: boolean; message: string }>("export_diagnostics", { path });
+    setStatus(result.message);
+  } catch (e) {
+    setStatus("Could not export diagnostics: " + String(e), true);
+  }
+}
+
+async function loadModProfiles() {
+  try {
+    const { profiles, cloud_error } = await invoke<{ profiles: ModProfile[]; cloud_error: string | null }>(
+      "list_mod_profiles"
+    );
+    modProfileListEl.replaceChildren(
+      ...profiles.map((p) => {
+        const option = document.createElement("option");
+        option.value = p.name;
+        // Offline, whether a profile is in the cloud is unknown; only say which are cached.
+        if (cloud_error) option.label = p.cached ? `${p.name} (cached)` : p.name;
+        else option.label = p.in_cloud ? p.name : `${p.name} (only on this PC)`;
+        return option;
+      })
+    );
+    modProfileEl.title = cloud_error ? `Profiles in the cloud could not be listed: ${cloud_error}` : "";
+    modProfileEl.value = profiles.find((p) => p.active)?.name ?? "default";
+  } catch {
+    modProfileEl.value = loadedConfig.mod_profile ?? "default";
+  }
+}
+
+async function switchModProfile() {
+  const profile = modProfileEl.value.trim() || "default";
+  setStatus(`Switching to mod profile ${profile}...`);
+  try {
+    const result = await invoke<{ ok: boolean; message: string }>("switch_mod_profile", { profile });
+    setStatus(result.message);
+  } catch (e) {
+    setStatus("Error: " + String(e), true);
+  }
+  // The backend saved the new profile in config; reload so "Save settings" keeps it.
+  await loadConfig();
+  await loadModProfiles();
+  await refreshSyncStatus();
+}
+
+/** Checks the user may go past for one sync; each warning turns on only its own. */
+interface Overrides {
+  allow_game_running?: boolean;
+  ignore_progress?: boolean;
+  ignore_setup_mismatch?: boolean;
+}
+
+// Errors the user may override by retrying: progress going backwards, game running, a save last
+// played on another game build or mod set.
+const FORCEABLE_PREFIXES: [string, keyof Overrides][] = [
+  ["PROGRESS_WARNING:", "ignore_progress"],
+  ["GAME_RUNNING:", "allow_game_running"],
+  ["SAVE_MISMATCH:", "ignore_setup_mismatch"],
+];
+
+function forceableWarning(msg: string): { text: string; override: keyof Overrides } | null {
+  const match = FORCEABLE_PREFIXES.find(([p]) => msg.includes(p));
+  if (!match) return null;
+  const [prefix, override] = match;
+  return { text: msg.slice(msg.indexOf(prefix) + prefix.length).trim(), override };
+}
+
+async function doSyncPull(target: SyncTarget = "both", overrides: Overrides = {}, successMessage?: string) {
   setStatus(target === "both" ? "Fetching..." : `Fetching ${target}...`);
   try {
     const result = await invoke<{ ok: boolean; message: string }>("do_sync_pull", {
       target: target === "both" ? undefined : target,
-      force: force || undefined,
+      overrides,
     });
     setStatus(successMessage && result.ok ? successMessage : result.message);
     await refreshSyncStatus();
   } catch (e) {
     const msg = String(e);
-    if (msg.includes("PROGRESS_WARNING:")) {
-      const warningText = msg.replace("PROGRESS_WARNING:", "").trim();
+    const warning = forceableWarning(msg);
+    if (warning != null) {
       setStatus("");
-      const confirmed = await showModal(warningText, "Fetch anyway");
+      const confirmed = await showModal(warning.text, "Fetch anyway");
       if (confirmed) {
-        await doSyncPull(target, true);
+        await doSyncPull(target, { ...overrides, [warning.override]: true }, successMessage);
       } else {
         setStatus("Fetch cancelled.");
       }
@@ -221,23 +935,25 @@ async function doSyncPull(target: SyncTarget = "both", force = false, successMes
   }
 }
 
-async function doSyncPush(target: SyncTarget = "both", force = false) {
+async function doSyncPush(target: SyncTarget = "both", overrides: Overrides = {}) {
   setStatus(target === "both" ? "Uploading..." : `Uploading ${target}...`);
   try {
     const result = await invoke<{ ok: boolean; message: string }>("do_sync_push", {
       target: target === "both" ? undefined : target,
-      force: force || undefined,
+      overrides,
+      note: pushNoteEl.value.trim() || undefined,
     });
+    pushNoteEl.value = "";
     setStatus(result.message);
     await refreshSyncStatus();
   } catch (e) {
     const msg = String(e);
-    if (msg.includes("PROGRESS_WARNING:")) {
-      const warningText = msg.replace("PROGRESS_WARNING:", "").trim();
+    const warning = forceableWarning(msg);
+    if (warning != null) {
       setStatus("");
-      const confirmed = await showModal(warningText);
+      const confirmed = await showModal(warning.text);
       if (confirmed) {
-        await doSyncPush(target, true);
+        await doSyncPush(target, { ...overrides, [warning.override]: true });
       } else {
         setStatus("Upload cancelled.");
       }
@@ -273,15 +989,24 @@ async function checkForAppUpdate() {
   }
 }
 
-function initStartupToggle() {
-  runAtStartupEl?.addEventListener("change", () => {
-    localStorage.setItem("syncone_run_at_startup", String(runAtStartupEl.checked));
-    setStatus(
-      runAtStartupEl.checked
-        ? "Remember to save paths. On next boot SyncONE will run at startup (if you added it to Startup)."
-        : "SyncONE will no longer run at startup."
-    );
-  });
+async function setRunAtStartup(): Promise<boolean> {
+  const enabled = runAtStartupEl.checked;
+  try {
+    await invoke("set_run_at_startup", { enabled });
+    loadedConfig.run_at_startup = enabled;
+    if (!(await warnLegacyStartup())) {
+      setStatus(
+        enabled
+          ? "SyncONE will start in the tray at login and fetch the latest save."
+          : "SyncONE will no longer run at startup."
+      );
+    }
+    return true;
+  } catch (e) {
+    runAtStartupEl.checked = !enabled;
+    setStatus("Could not change run at startup: " + String(e), true);
+    return false;
+  }
 }
 
 window.addEventListener("DOMContentLoaded", async () => {
@@ -298,9 +1023,12 @@ window.addEventListener("DOMContentLoaded", async () => {
   void checkForAppUpdate();
 
   await loadConfig();
+  await migrateLegacyStartup();
   bindBrowse(browseSaveBtn, savePathEl);
   bindBrowse(browseModsBtn, modsPathEl);
   bindBrowse(browseCloudBtn, cloudPathEl);
+  bindBrowse(browseGameRootBtn, gameRootEl);
+  detectPathsBtn?.addEventListener("click", () => void detectPaths());
   saveConfigBtn?.addEventListener("click", saveConfig);
   syncPullBtn?.addEventListener("click", () => doSyncPull("both"));
   syncPushBtn?.addEventListener("click", () => doSyncPush("both"));
@@ -310,27 +1038,78 @@ window.addEventListener("DOMContentLoaded", async () => {
     if (!btn) return;
     const target = (btn as HTMLButtonElement).dataset.target as SyncTarget;
     if ((btn as HTMLButtonElement).classList.contains("card-fetch")) {
-      void doSyncPull(target);
+      if (target === "mods") {
+        void confirmModChanges().then((ok) => (ok ? doSyncPull(target) : setStatus("Fetch cancelled.")));
+      } else {
+        void doSyncPull(target);
+      }
     } else if ((btn as HTMLButtonElement).classList.contains("card-upload")) {
       void doSyncPush(target);
     }
   });
 
-  initStartupToggle();
-  openStartupBtn?.addEventListener("click", async () => {
-    try {
-      await invoke("open_startup_folder");
-    } catch (e) {
-      setStatus("Could not open folder: " + String(e), true);
-    }
-  });
+  runAtStartupEl?.addEventListener("change", () => void setRunAtStartup());
 
   refreshStatusBtn?.addEventListener("click", refreshSyncStatus);
+  switchModProfileBtn?.addEventListener("click", switchModProfile);
+  refreshActivityBtn?.addEventListener("click", () => void loadActivity());
+  exportDiagnosticsBtn?.addEventListener("click", () => void exportDiagnostics());
+  handoffBtn?.addEventListener("click", () => void doHandoff());
+  activityEl?.addEventListener("toggle", () => {
+    if (activityEl.open) void loadActivity();
+  });
+  versionsEl?.addEventListener("toggle", () => {
+    if (versionsEl.open) void loadVersions();
+  });
+  collectGarbageBtn?.addEventListener("click", () => void collectGarbage());
+  void loadModProfiles();
   await refreshSyncStatus();
 
-  // Refresh status every 30 seconds so local folder changes (e.g. after saving in-game) are picked up
+  // Refresh status every 30 seconds so local folder changes (e.g. after saving in-game) are picked up.
+  // While the backend watches the cloud, pushes by others arrive as "cloud-changed" events and
+  // the poll only has to catch local changes, so it slows down.
   const REFRESH_INTERVAL_MS = 30_000;
-  setInterval(() => void refreshSyncStatus(), REFRESH_INTERVAL_MS);
+  const WATCHED_REFRESH_INTERVAL_MS = 5 * 60_000;
+  let cloudWatched = await invoke<boolean>("get_cloud_watch_active").catch(() => false);
+  let lastStatusRefresh = Date.now();
+  setInterval(() => {
+    const interval = cloudWatched ? WATCHED_REFRESH_INTERVAL_MS : REFRESH_INTERVAL_MS;
+    if (Date.now() - lastStatusRefresh < interval) return;
+    lastStatusRefresh = Date.now();
+    void refreshSyncStatus();
+  }, REFRESH_INTERVAL_MS);
+  await listen<boolean>("cloud-watch", (event) => {
+    cloudWatched = event.payload;
+  });
+  await listen<{ target: string; message: string }>("cloud-changed", (event) => {
+    setStatus(event.payload.message);
+    lastStatusRefresh = Date.now();
+    void refreshSyncStatus();
+  });
+  void loadPresence();
+  setInterval(() => void loadPresence(), REFRESH_INTERVAL_MS);
+  void checkHandoff();
+  setInterval(() => void checkHandoff(), REFRESH_INTERVAL_MS);
+  await listen<HostLease>("handoff-offered", (event) => void offerHandoff(event.payload));
+  await listen<HostLease>("host-changed", () => void loadPresence());
+  // Fetch / upload started from the tray menu; a warning opened the window to ask here.
+  await listen<{ action: string; ok: boolean; message: string; warning: boolean }>("sync-finished", async (event) => {
+    const { action, ok, message, warning } = event.payload;
+    const forceable = warning ? forceableWarning(message) : null;
+    if (forceable != null) {
+      const isFetch = action === "fetch";
+      if (await showModal(forceable.text, isFetch ? "Fetch anyway" : "Upload anyway")) {
+        const overrides: Overrides = {};
+        overrides[forceable.override] = true;
+        await (isFetch ? doSyncPull("both", overrides) : doSyncPush("both", overrides));
+      } else {
+        setStatus(isFetch ? "Fetch cancelled." : "Upload cancelled.");
+      }
+      return;
+    }
+    setStatus(ok ? message : "Error: " + message, !ok);
+    await refreshSyncStatus();
+  });
   document.addEventListener("visibilitychange", () => {
     if (document.visibilityState === "visible") void refreshSyncStatus();
   });
@@ -338,9 +1117,13 @@ window.addEventListener("DOMContentLoaded", async () => {
 
   const hasCloud =
     (supabaseUrlEl.value.trim() && supabaseKeyEl.value.trim() && bucketNameEl.value.trim()) ||
-    cloudPathEl.value.trim();
-  if (savePathEl.value.trim() && modsPathEl.value.trim() && hasCloud) {
+    cloudPathEl.value.trim() ||
+    gitRemoteEl.value.trim();
+  // Started at login with --auto-pull: the backend fetches and reports it as "sync-finished".
+  const launch = await invoke<{ minimized: boolean; auto_pull: boolean }>("get_launch_options").catch(() => null);
+  if (launch?.auto_pull) return;
+  if (savePathEl.value.trim() && (modsPathEl.value.trim() || gameRootEl.value.trim()) && hasCloud) {
     setStatus("Checking for updates...");
-    await doSyncPull("both", false, "Auto pulled newest version on startup");
+    await doSyncPull("both", {}, "Auto pulled newest version on startup");
   }
 });